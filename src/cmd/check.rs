@@ -0,0 +1,51 @@
+use std::path::Path;
+
+use color_eyre::eyre::{eyre, Result};
+
+use crate::db;
+use crate::db::check::Report;
+use crate::db::event::Schedule;
+use crate::db::menu::Menu;
+use crate::db::store::Lines;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Write an id for each guest missing one, if the guestlist is valid.
+    #[arg(long)]
+    assign_ids: bool,
+}
+
+pub fn run(args: Args, path: &Path, menu: &Menu, schedule: &Schedule) -> Result<()> {
+    // Read the guestlist
+    let Lines { entries, missing } = db::read(path, schedule)?;
+    // Validate the guests
+    let report = Report::new(&entries, menu, schedule);
+    for issue in report.issues() {
+        println!("{issue}");
+    }
+    for line in &missing {
+        println!("line {line}: missing id");
+    }
+    println!(
+        "checked {} guests: {} errors, {} warnings",
+        entries.len(),
+        report.errors(),
+        report.warnings()
+    );
+    // Fail on any errors
+    if report.errors() > 0 {
+        return Err(eyre!("invalid guestlist: {} errors", report.errors()));
+    }
+    // Assign ids to any guests missing one (if requested)
+    match missing.len() {
+        0 => Ok(()),
+        _ if args.assign_ids => {
+            let count = db::assign(path)?;
+            println!("assigned {count} ids");
+            Ok(())
+        }
+        n => Err(eyre!(
+            "{n} guests missing ids, rerun with `--assign-ids` to assign them"
+        )),
+    }
+}
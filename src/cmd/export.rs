@@ -0,0 +1,32 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use clap::ValueHint;
+use color_eyre::eyre::Result;
+
+use crate::db::export::{self, Format};
+use crate::db::Database;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Format of the export.
+    #[arg(value_enum)]
+    format: Format,
+
+    /// Path to write the export [default: stdout].
+    #[arg(value_hint = ValueHint::FilePath)]
+    path: Option<PathBuf>,
+}
+
+pub fn run(args: Args, db: &Database) -> Result<()> {
+    // Export the database
+    let data = export::export(db, args.format)?;
+    // Write it out
+    match args.path {
+        Some(path) => fs::write(path, data)?,
+        None => io::stdout().write_all(&data)?,
+    }
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use std::io;
+use std::time::Duration;
+
+use color_eyre::eyre::Result;
+use itertools::Itertools;
+
+use crate::db::Database;
+use crate::srv::link::Signer;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Base URL of the server.
+    #[arg(long)]
+    url: String,
+
+    /// Days until links expire.
+    #[arg(long)]
+    #[arg(default_value_t = 180)]
+    days: u64,
+}
+
+pub fn run(args: Args, db: &Database, signer: &Signer) -> Result<()> {
+    let ttl = Duration::from_secs(args.days * 24 * 60 * 60);
+    let base = args.url.trim_end_matches('/');
+    // Write links as CSV
+    let mut writer = csv::Writer::from_writer(io::stdout());
+    writer.write_record(["group", "guests", "url"])?;
+    for (group, idents) in db.groups() {
+        // Collect the group's guests
+        let guests = idents
+            .iter()
+            .filter_map(|ident| db.guest(ident))
+            .collect::<Vec<_>>();
+        // Sign a link for the group's first guest
+        let Some(guest) = guests.first() else {
+            continue;
+        };
+        let token = signer.sign(&guest.user().ident, ttl);
+        let url = format!("{base}/login/link/{token}");
+        // Write the group's link
+        let names = guests.iter().map(|guest| guest.user().name()).join(", ");
+        writer.write_record([group.to_string(), names, url])?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use askama::Template;
+use clap::ValueHint;
+use color_eyre::eyre::{bail, eyre, Context, Result};
+use lettre::message::Mailbox;
+
+use crate::db::Database;
+use crate::mail::campaign::{self, Invitation, Kind, Log, Reminder};
+use crate::mail::{self, Mailer, Smtp};
+use crate::srv::link::Signer;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Kind of email to send.
+    #[arg(value_enum)]
+    kind: Kind,
+
+    /// Base URL of the server.
+    #[arg(long)]
+    url: String,
+
+    /// Days until links expire.
+    #[arg(long)]
+    #[arg(default_value_t = 180)]
+    days: u64,
+
+    /// Name of the campaign [default: kind of email].
+    #[arg(long)]
+    campaign: Option<String>,
+
+    /// Path to the log of sent emails.
+    #[arg(long)]
+    #[arg(default_value = "sent.csv")]
+    #[arg(value_hint = ValueHint::FilePath)]
+    sent: PathBuf,
+
+    /// Write emails to a directory instead of sending them.
+    #[arg(long)]
+    #[arg(value_hint = ValueHint::DirPath)]
+    dry_run: Option<PathBuf>,
+}
+
+/// Where emails go: out to the relay, or into a directory on a dry run.
+enum Outbox<'a> {
+    Smtp(Box<Mailer>),
+    Dir(&'a Path),
+}
+
+/// Sender of emails previewed without any smtp config.
+const PREVIEW: &str = "wedding@localhost";
+
+pub async fn run(args: Args, db: &Database, signer: &Signer, smtp: Option<&Smtp>) -> Result<()> {
+    let ttl = Duration::from_secs(args.days * 24 * 60 * 60);
+    let base = args.url.trim_end_matches('/');
+    let campaign = args
+        .campaign
+        .unwrap_or_else(|| args.kind.name().to_string());
+    // Prepare the mailer, unless only writing emails out
+    let outbox = match &args.dry_run {
+        Some(dir) => {
+            fs::create_dir_all(dir)?;
+            Outbox::Dir(dir)
+        }
+        None => {
+            let smtp = smtp.ok_or_else(|| eyre!("missing smtp config"))?;
+            Outbox::Smtp(Box::new(
+                Mailer::new(smtp).context("failed to configure smtp")?,
+            ))
+        }
+    };
+    let from: Mailbox = smtp
+        .map_or(PREVIEW, |smtp| smtp.from.as_str())
+        .parse()
+        .context("invalid sender address")?;
+    let mut log = Log::open(&args.sent).context("failed to open sent log")?;
+    let mut count = 0;
+    let mut failed = Vec::new();
+    for (group, idents) in db.groups() {
+        // Collect the group's guests
+        let guests = idents
+            .iter()
+            .filter_map(|ident| db.guest(ident))
+            .collect::<Vec<_>>();
+        // Skip groups this kind of email isn't for
+        if !args.kind.wants(&guests) {
+            continue;
+        }
+        // Address guests not yet emailed in this campaign
+        let to = guests
+            .iter()
+            .filter_map(|guest| guest.email())
+            .filter(|email| !log.sent(&campaign, email))
+            .collect::<Vec<_>>();
+        let Some(guest) = guests.first().filter(|_| !to.is_empty()) else {
+            continue;
+        };
+        // Sign a link for the group's first guest
+        let token = signer.sign(&guest.user().ident, ttl);
+        let url = format!("{base}/login/link/{token}");
+        // Render the email
+        let names = campaign::names(&guests);
+        let body = match args.kind {
+            Kind::Invite => Invitation {
+                names: &names,
+                url: &url,
+                deadline: db.deadline,
+            }
+            .render()?,
+            Kind::Remind => Reminder {
+                names: &names,
+                guests: &guests,
+                url: &url,
+                deadline: db.deadline,
+            }
+            .render()?,
+        };
+        let msg = match mail::message(&from, &to, args.kind.subject(), body) {
+            Ok(msg) => msg,
+            Err(err) => {
+                eprintln!("warning: failed to email group {group}: {err}");
+                failed.push(group);
+                continue;
+            }
+        };
+        // Send the email, or write it out on a dry run
+        match &outbox {
+            Outbox::Dir(dir) => {
+                let path = dir.join(format!("{campaign}-{group}.eml"));
+                fs::write(path, msg.formatted())?;
+            }
+            Outbox::Smtp(mailer) => {
+                if let Err(err) = mailer.send(msg).await {
+                    eprintln!("warning: failed to email group {group}: {err}");
+                    failed.push(group);
+                    continue;
+                }
+                for email in &to {
+                    log.record(&campaign, group, email)?;
+                }
+            }
+        }
+        println!("{campaign}: group {group}: {}", to.join(", "));
+        count += 1;
+    }
+    match outbox {
+        Outbox::Dir(dir) => println!("wrote {count} emails to `{}`", dir.display()),
+        Outbox::Smtp(_) => println!("sent {count} emails"),
+    }
+    // Report any groups left unsent
+    if !failed.is_empty() {
+        let groups = failed.iter().map(ToString::to_string).collect::<Vec<_>>();
+        bail!("failed to email groups: {}", groups.join(", "));
+    }
+
+    Ok(())
+}
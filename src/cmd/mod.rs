@@ -0,0 +1,67 @@
+use clap::Subcommand;
+use color_eyre::eyre::{eyre, Result};
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::srv::link::Signer;
+
+pub mod check;
+pub mod export;
+pub mod hash;
+pub mod link;
+pub mod mail;
+pub mod seat;
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Check the guestlist for problems.
+    Check(check::Args),
+    #[command(flatten)]
+    Task(Task),
+}
+
+/// Commands run against the loaded database.
+#[derive(Subcommand)]
+pub enum Task {
+    /// Export the guestlist and RSVP results.
+    Export(export::Args),
+    /// Hash an admin password read from stdin.
+    Hash(hash::Args),
+    /// Generate signed login links for each group.
+    Link(link::Args),
+    /// Email invitations or reminders to each group.
+    Mail(mail::Args),
+    /// Plan or import seating at tables.
+    Seat(seat::Args),
+}
+
+impl Task {
+    /// Whether the task writes to the database, rather than reading a snapshot.
+    pub fn writes(&self) -> bool {
+        match self {
+            Task::Export(_) | Task::Hash(_) | Task::Link(_) | Task::Mail(_) => false,
+            Task::Seat(args) => args.writes(),
+        }
+    }
+
+    pub async fn run(
+        self,
+        db: &mut Database,
+        signer: Option<&Signer>,
+        config: &Config,
+    ) -> Result<()> {
+        match self {
+            Task::Export(args) => export::run(args, db),
+            Task::Hash(args) => hash::run(args),
+            Task::Link(args) => {
+                let signer = signer.ok_or_else(|| eyre!("missing link key"))?;
+                link::run(args, db, signer)
+            }
+            Task::Mail(args) => {
+                let signer = signer.ok_or_else(|| eyre!("missing link key"))?;
+                mail::run(args, db, signer, config.smtp.as_ref()).await
+            }
+            Task::Seat(args) => seat::run(args, db, &config.seating),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use clap::{Subcommand, ValueHint};
+use color_eyre::eyre::{bail, Context, Result};
+
+use crate::db::plan::{self, Constraints, Seat};
+use crate::db::{Database, Ident};
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(subcommand)]
+    action: Action,
+}
+
+#[derive(Subcommand)]
+enum Action {
+    /// Propose a seating plan for review.
+    Plan {
+        /// Path to write the plan [default: stdout].
+        #[arg(value_hint = ValueHint::FilePath)]
+        path: Option<PathBuf>,
+
+        /// Also seat guests who haven't replied.
+        #[arg(long)]
+        pending: bool,
+    },
+    /// Import a reviewed seating plan.
+    ///
+    /// Stop the server first, as it would overwrite the imported seats with
+    /// its own copy of the guestlist.
+    Import {
+        /// Path to the plan to import.
+        #[arg(value_hint = ValueHint::FilePath)]
+        path: PathBuf,
+    },
+}
+
+impl Args {
+    pub fn writes(&self) -> bool {
+        matches!(self.action, Action::Import { .. })
+    }
+}
+
+pub fn run(args: Args, db: &mut Database, constraints: &Constraints) -> Result<()> {
+    match args.action {
+        Action::Plan { path, pending } => propose(db, constraints, path, pending),
+        Action::Import { path } => import(db, path),
+    }
+}
+
+fn propose(
+    db: &Database,
+    constraints: &Constraints,
+    path: Option<PathBuf>,
+    pending: bool,
+) -> Result<()> {
+    if db.tables.tables().is_empty() {
+        bail!("no tables configured");
+    }
+    // Solve for a seating plan
+    let plan = plan::plan(db, constraints, pending);
+    // Write out each guest's seat
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for seat in &plan.seats {
+        writer.serialize(seat)?;
+    }
+    let data = writer.into_inner()?;
+    match path {
+        Some(path) => fs::write(path, data)?,
+        None => io::stdout().write_all(&data)?,
+    }
+    // Report the plan's score and violations
+    eprintln!("seated {} guests, score: {}", plan.seats.len(), plan.score);
+    for violation in &plan.violations {
+        eprintln!("warning: {violation} (+{})", violation.penalty());
+    }
+
+    Ok(())
+}
+
+fn import(db: &mut Database, path: PathBuf) -> Result<()> {
+    // Read each guest's seat from the plan
+    let mut reader = csv::Reader::from_path(&path).context("failed to open plan")?;
+    let seats = reader
+        .deserialize()
+        .collect::<Result<Vec<Seat>, _>>()
+        .context("failed to read plan")?;
+    // Validate the whole plan before seating anyone
+    let mut tables: HashMap<Ident, Option<&str>> = HashMap::new();
+    for Seat { id, table, .. } in &seats {
+        if db.guest(id).is_none() {
+            bail!("unknown guest `{id}`");
+        }
+        let table = table.as_deref().filter(|table| !table.trim().is_empty());
+        if let Some(table) = table {
+            if db.tables.get(table).is_none() {
+                bail!("unknown table `{table}` for guest `{id}`");
+            }
+        }
+        tables.insert(*id, table);
+    }
+    // Check no table would be over capacity
+    let mut attending: HashMap<&str, usize> = HashMap::new();
+    let guests = db
+        .groups()
+        .flat_map(|(_, idents)| idents)
+        .filter_map(|ident| db.guest(ident));
+    for guest in guests {
+        let ident = guest.user().ident;
+        let table = tables.get(&ident).copied().unwrap_or(guest.table());
+        if let Some(table) = table {
+            if guest.reply().attends(db.tables.event()) {
+                *attending.entry(table).or_default() += 1;
+            }
+        }
+    }
+    for table in db.tables.tables() {
+        let seated = attending
+            .get(table.name.as_str())
+            .copied()
+            .unwrap_or_default();
+        if seated > table.capacity {
+            bail!(
+                "{} would be over capacity: {seated} / {}",
+                table.name,
+                table.capacity
+            );
+        }
+    }
+    // Seat each guest
+    let count = seats.len();
+    for Seat { id, table, .. } in seats {
+        db.seat(&id, table)
+            .with_context(|| format!("failed to seat guest `{id}`"))?;
+    }
+    // Save the updated seating
+    db.write().context("failed to write database")?;
+    println!("imported {count} seats from `{}`", path.display());
+    // Report any problems with the seating
+    for warning in db.chart().warnings {
+        eprintln!("warning: {warning}");
+    }
+
+    Ok(())
+}
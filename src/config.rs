@@ -0,0 +1,67 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use itertools::Itertools;
+use log::debug;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::db::event::Event;
+use crate::db::menu::Choice;
+use crate::db::plan::Constraints;
+use crate::db::seating::Table;
+use crate::mail::Smtp;
+use crate::metrics::Scraper;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub admin: Vec<Admin>,
+    #[serde(default)]
+    pub event: Vec<Event>,
+    #[serde(default)]
+    pub meal: Vec<Choice>,
+    #[serde(default)]
+    pub table: Vec<Table>,
+    #[serde(default)]
+    pub seating: Constraints,
+    pub smtp: Option<Smtp>,
+    pub metrics: Option<Scraper>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Admin {
+    pub name: String,
+    pub password: String,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        debug!("config: reading: `{}`", path.display());
+        let text = fs::read_to_string(path)?;
+        let config: Self = toml::from_str(&text)?;
+        // Reject meals sharing an id, as replies couldn't tell them apart
+        if let Some(meal) = config
+            .meal
+            .iter()
+            .map(|choice| &choice.id)
+            .duplicates()
+            .next()
+        {
+            return Err(Error::Meal(meal.to_string()));
+        }
+
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+    #[error("duplicate meal: `{0}`")]
+    Meal(String),
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use lettre::Address;
+use log::{error, warn};
+
+use super::event::Schedule;
+use super::guest::Guest;
+use super::menu::Menu;
+use super::{Group, Ident};
+use crate::user::{self, User};
+
+#[derive(Debug, Default)]
+pub struct Report {
+    issues: Vec<Issue>,
+}
+
+impl Report {
+    pub fn new(entries: &[(u64, Guest)], menu: &Menu, schedule: &Schedule) -> Self {
+        let mut report = Self::default();
+        let mut names: HashMap<&User, u64> = HashMap::new();
+        let mut idents: HashMap<Ident, u64> = HashMap::new();
+        let mut groups: HashMap<Group, (u64, bool)> = HashMap::new();
+        let mut codes: HashMap<String, (Group, u64)> = HashMap::new();
+        let mut hosts: Vec<(u64, Ident)> = Vec::new();
+
+        for (line, guest) in entries {
+            let line = *line;
+            let user = guest.user();
+            // Check for empty names
+            match (
+                user.first().trim().is_empty(),
+                user.last().trim().is_empty(),
+            ) {
+                (true, true) => report.push(line, Problem::Empty),
+                (true, false) | (false, true) => report.push(line, Problem::Partial(user.name())),
+                (false, false) => (),
+            }
+            // Check for duplicate names
+            if let Some(&other) = names.get(user) {
+                report.push(line, Problem::Name(user.name(), other));
+            } else {
+                names.insert(user, line);
+            }
+            // Check for duplicate identifiers
+            if let Some(&other) = idents.get(&user.ident) {
+                report.push(line, Problem::Ident(user.ident, other));
+            } else {
+                idents.insert(user.ident, line);
+            }
+            // Check invite codes aren't shared between groups
+            if let Some(code) = guest.code() {
+                let (group, other) = *codes
+                    .entry(user::sanitize(code))
+                    .or_insert((guest.group(), line));
+                if group != guest.group() {
+                    report.push(line, Problem::Code(code.to_string(), group, other));
+                }
+            }
+            // Track whether each group has an adult
+            let (_, adult) = groups.entry(guest.group()).or_insert((line, false));
+            *adult |= !guest.child();
+            // Check the email address is valid
+            if let Some(email) = guest.email() {
+                if email.parse::<Address>().is_err() {
+                    report.push(line, Problem::Email(email.to_string()));
+                }
+            }
+            // Track who brought each companion
+            if let Some(host) = guest.host() {
+                hosts.push((line, *host));
+            }
+            // Check for unknown events
+            for event in guest.events().iter() {
+                if schedule.get(event).is_none() {
+                    report.push(line, Problem::Event(event.clone()));
+                }
+            }
+            // Check the reply is consistent
+            let mut reply = guest.reply().clone();
+            reply.validate(guest, menu, schedule);
+            let original = guest.reply();
+            if reply.events != original.events
+                || reply.diet != original.diet
+                || reply.note.is_some() != original.note.is_some()
+            {
+                report.push(line, Problem::Reply(user.name()));
+            }
+        }
+        // Check for groups of only children
+        let mut groups: Vec<_> = groups.into_iter().collect();
+        groups.sort_by_key(|(_, (line, _))| *line);
+        for (group, (line, adult)) in groups {
+            if !adult {
+                report.push(line, Problem::Children(group));
+            }
+        }
+        // Check for companions without a host
+        for (line, host) in hosts {
+            if !idents.contains_key(&host) {
+                report.push(line, Problem::Host(host));
+            }
+        }
+        report.issues.sort_by_key(|issue| issue.line);
+
+        report
+    }
+
+    fn push(&mut self, line: u64, problem: Problem) {
+        self.issues.push(Issue { line, problem });
+    }
+
+    pub fn issues(&self) -> &[Issue] {
+        &self.issues
+    }
+
+    pub fn errors(&self) -> usize {
+        self.issues.iter().filter(|issue| issue.fatal()).count()
+    }
+
+    pub fn warnings(&self) -> usize {
+        self.issues.len() - self.errors()
+    }
+
+    pub fn log(&self) {
+        for issue in &self.issues {
+            if issue.fatal() {
+                error!("check: {issue}");
+            } else {
+                warn!("check: {issue}");
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Issue {
+    line: u64,
+    problem: Problem,
+}
+
+impl Issue {
+    pub fn fatal(&self) -> bool {
+        matches!(
+            self.problem,
+            Problem::Empty | Problem::Name(..) | Problem::Ident(..) | Problem::Code(..)
+        )
+    }
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let level = if self.fatal() { "error" } else { "warning" };
+        write!(f, "line {}: {level}: {}", self.line, self.problem)
+    }
+}
+
+#[derive(Debug)]
+pub enum Problem {
+    Empty,
+    Partial(String),
+    Name(String, u64),
+    Ident(Ident, u64),
+    Code(String, Group, u64),
+    Children(Group),
+    Event(String),
+    Email(String),
+    Host(Ident),
+    Reply(String),
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::Empty => write!(f, "empty name"),
+            Problem::Partial(name) => write!(f, "empty first or last name: `{name}`"),
+            Problem::Name(name, line) => {
+                write!(f, "duplicate name: `{name}` (first on line {line})")
+            }
+            Problem::Ident(ident, line) => {
+                write!(f, "duplicate id: `{ident}` (first on line {line})")
+            }
+            Problem::Code(code, group, line) => {
+                write!(
+                    f,
+                    "duplicate code: `{code}` (used by group {group} on line {line})"
+                )
+            }
+            Problem::Children(group) => write!(f, "group {group} has only children"),
+            Problem::Event(event) => write!(f, "unknown event: `{event}`"),
+            Problem::Email(email) => write!(f, "invalid email: `{email}`"),
+            Problem::Host(ident) => write!(f, "unknown host: `{ident}`"),
+            Problem::Reply(name) => {
+                write!(f, "inconsistent reply: `{name}`")
+            }
+        }
+    }
+}
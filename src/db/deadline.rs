@@ -0,0 +1,44 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, FixedOffset, Utc};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Deadline(DateTime<FixedOffset>);
+
+impl Deadline {
+    pub fn passed(&self) -> bool {
+        Utc::now() >= self.0
+    }
+
+    pub fn date(&self) -> String {
+        // Format as a month and ordinal day, e.g. "May 1st"
+        let day = self.0.day();
+        let suffix = match (day % 10, day % 100) {
+            (_, 11..=13) => "th",
+            (1, _) => "st",
+            (2, _) => "nd",
+            (3, _) => "rd",
+            _ => "th",
+        };
+        format!("{} {day}{suffix}", self.0.format("%B"))
+    }
+
+    pub fn iso(&self) -> String {
+        self.0.to_rfc3339()
+    }
+}
+
+impl Display for Deadline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl FromStr for Deadline {
+    type Err = chrono::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DateTime::parse_from_rfc3339(s).map(Self)
+    }
+}
@@ -0,0 +1,104 @@
+use serde::Deserialize;
+
+use super::guest::Guest;
+
+/// Event used for replies recorded before events were configurable.
+pub const DEFAULT: &str = "wedding";
+
+#[derive(Clone, Debug)]
+pub struct Schedule {
+    events: Vec<Event>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Event {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub meals: bool,
+    /// Answered by replies recorded before events were configurable.
+    #[serde(default)]
+    pub legacy: bool,
+    /// Seated at tables, by default the first event with meals.
+    #[serde(default)]
+    pub seated: bool,
+}
+
+impl Schedule {
+    pub fn new(events: Vec<Event>) -> Self {
+        // Fall back to the default schedule when none is configured
+        if events.is_empty() {
+            return Self::default();
+        }
+        Self { events }
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    pub fn ids(&self) -> Vec<String> {
+        self.events.iter().map(|event| event.id.clone()).collect()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Event> {
+        self.events.iter().find(|event| event.id == id)
+    }
+
+    pub fn seated(&self) -> Option<&Event> {
+        // Prefer the marked event, then one with meals
+        self.events
+            .iter()
+            .find(|event| event.seated)
+            .or_else(|| self.events.iter().find(|event| event.meals))
+            .or_else(|| self.events.first())
+    }
+
+    pub fn invited<'a>(&'a self, guest: &'a Guest) -> impl Iterator<Item = &'a Event> {
+        self.events
+            .iter()
+            .filter(move |event| guest.invited(&event.id))
+    }
+
+    pub fn migrate(&self, guest: &mut Guest) {
+        // Keep replies to the default event if still scheduled
+        if self.get(DEFAULT).is_some() {
+            return;
+        }
+        let Some(answer) = guest.reply.events.remove(DEFAULT) else {
+            return;
+        };
+        // Answer the legacy events instead, or every event if none are marked
+        let marked = self.events.iter().any(|event| event.legacy);
+        for event in self.events.iter().filter(|event| event.legacy || !marked) {
+            guest
+                .reply
+                .events
+                .entry(event.id.clone())
+                .or_insert_with(|| answer.clone());
+        }
+    }
+
+    pub fn answered(&self, guest: &Guest) -> bool {
+        self.invited(guest).all(|event| {
+            guest
+                .reply()
+                .answer(&event.id)
+                .is_some_and(|answer| answer.attend.is_some())
+        })
+    }
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self {
+            events: vec![Event {
+                id: DEFAULT.to_string(),
+                name: "Wedding".to_string(),
+                meals: true,
+                legacy: true,
+                seated: true,
+            }],
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use std::fmt::Write;
+
+use clap::ValueEnum;
+use indexmap::IndexMap;
+use rust_xlsxwriter::{Format as Style, Workbook};
+use serde::Deserialize;
+
+use super::guest::Guest;
+use super::record::Record;
+use super::{Database, Error, Group};
+
+#[derive(Clone, Copy, Debug, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// Guestlist as JSON.
+    Json,
+    /// Guestlist and meal counts as a spreadsheet.
+    Xlsx,
+    /// Meal counts and dietary notes for the caterer.
+    Caterer,
+}
+
+impl Format {
+    pub fn mime(self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            Format::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            Format::Caterer => "text/plain; charset=utf-8",
+        }
+    }
+
+    pub fn filename(self) -> &'static str {
+        match self {
+            Format::Json => "guests.json",
+            Format::Xlsx => "guests.xlsx",
+            Format::Caterer => "caterer.txt",
+        }
+    }
+}
+
+pub fn export(db: &Database, format: Format) -> Result<Vec<u8>, Error> {
+    match format {
+        Format::Json => json(db),
+        Format::Xlsx => xlsx(db),
+        Format::Caterer => Ok(caterer(db).into_bytes()),
+    }
+}
+
+fn json(db: &Database) -> Result<Vec<u8>, Error> {
+    // Serialize each guest as a record
+    let records: Vec<_> = db.guests.values().cloned().map(Record::from).collect();
+    Ok(serde_json::to_vec_pretty(&records)?)
+}
+
+fn xlsx(db: &Database) -> Result<Vec<u8>, Error> {
+    let mut book = Workbook::new();
+    let bold = Style::new().set_bold();
+    // Write the guestlist
+    let sheet = book.add_worksheet().set_name("Guests")?;
+    let mut header: Vec<String> = ["Group", "First", "Last", "Child", "Table"]
+        .map(String::from)
+        .to_vec();
+    for event in db.schedule.events() {
+        header.push(event.name.clone());
+        if event.meals {
+            header.push(format!("{} Meal", event.name));
+        }
+    }
+    header.extend(["Dietary", "Note", "Message"].map(String::from));
+    sheet.write_row_with_format(0, 0, header, &bold)?;
+    for (row, guest) in (1..).zip(db.guests.values()) {
+        let reply = guest.reply();
+        sheet.write(row, 0, guest.group() as u32)?;
+        sheet.write(row, 1, guest.user().first())?;
+        sheet.write(row, 2, guest.user().last())?;
+        sheet.write(row, 3, guest.child())?;
+        sheet.write(row, 4, guest.table().unwrap_or_default())?;
+        let mut col = 5;
+        for event in db.schedule.events() {
+            let answer = reply.answer(&event.id).cloned().unwrap_or_default();
+            let attend = match answer.attend {
+                _ if !guest.invited(&event.id) => "Not invited".to_string(),
+                Some(attend) => attend.to_string(),
+                None => String::new(),
+            };
+            sheet.write(row, col, attend)?;
+            col += 1;
+            if event.meals {
+                let meal = answer.meal.as_ref().map_or("", |meal| db.menu.label(meal));
+                sheet.write(row, col, meal)?;
+                col += 1;
+            }
+        }
+        let note = reply.note.as_deref().map_or("", String::as_str);
+        let msg = reply.msg.as_deref().map_or("", String::as_str);
+        sheet.write(row, col, reply.diet.labels())?;
+        sheet.write(row, col + 1, note)?;
+        sheet.write(row, col + 2, msg)?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    sheet.autofit();
+    // Write the meal counts of each event
+    let stats = db.stats();
+    let sheet = book.add_worksheet().set_name("Meals")?;
+    sheet.write_row_with_format(0, 0, ["Event", "Meal", "Count"], &bold)?;
+    let meals = stats.events.iter().flat_map(|tally| {
+        tally
+            .meals
+            .values()
+            .map(|serving| (&tally.event.name, serving))
+    });
+    for (row, (event, serving)) in (1..).zip(meals) {
+        sheet.write(row, 0, event)?;
+        sheet.write(row, 1, &serving.label)?;
+        sheet.write(row, 2, serving.count as u32)?;
+    }
+    sheet.autofit();
+    // Write the dietary counts
+    let sheet = book.add_worksheet().set_name("Dietary")?;
+    sheet.write_row_with_format(0, 0, ["Restriction", "Count"], &bold)?;
+    for (row, (diet, count)) in (1..).zip(&stats.diets) {
+        sheet.write(row, 0, diet.label())?;
+        sheet.write(row, 1, *count as u32)?;
+    }
+    sheet.autofit();
+
+    Ok(book.save_to_buffer()?)
+}
+
+fn caterer(db: &Database) -> String {
+    let stats = db.stats();
+    let mut out = String::new();
+    // Summarize the meals of each event
+    for tally in stats.events.iter().filter(|tally| tally.event.meals) {
+        writeln!(out, "Meals: {}", tally.event.name).unwrap();
+        for serving in tally.meals.values() {
+            writeln!(out, "  {}: {}", serving.label, serving.count).unwrap();
+        }
+        let total: usize = tally.meals.values().map(|serving| serving.count).sum();
+        writeln!(out, "  Total: {total}\n").unwrap();
+    }
+    // Summarize the dietary restrictions
+    writeln!(out, "Dietary").unwrap();
+    for (diet, count) in stats.diets.iter().filter(|(_, count)| **count > 0) {
+        writeln!(out, "  {}: {count}", diet.label()).unwrap();
+    }
+    if stats.diets.values().all(|count| *count == 0) {
+        writeln!(out, "  None").unwrap();
+    }
+    // List the notes of attending guests by group
+    let mut notes: IndexMap<Group, Vec<&Guest>> = IndexMap::new();
+    for guest in db.guests.values() {
+        let reply = guest.reply();
+        let attending = reply.attending();
+        // Include messages, where allergies were noted before dietary details
+        let noted = !reply.diet.is_empty()
+            || [&reply.note, &reply.msg]
+                .into_iter()
+                .flatten()
+                .any(|note| !note.trim().is_empty());
+        if attending && noted {
+            notes.entry(guest.group()).or_default().push(guest);
+        }
+    }
+    notes.sort_keys();
+    writeln!(out, "\nNotes").unwrap();
+    if notes.is_empty() {
+        writeln!(out, "  None").unwrap();
+    }
+    for (group, guests) in notes {
+        writeln!(out, "  Group {group}").unwrap();
+        for guest in guests {
+            let reply = guest.reply();
+            let meals = db
+                .schedule
+                .events()
+                .iter()
+                .filter_map(|event| {
+                    let meal = reply.answer(&event.id)?.meal.as_ref()?;
+                    Some(format!("{}: {}", event.name, db.menu.label(meal)))
+                })
+                .collect::<Vec<_>>();
+            let notes = [
+                reply.diet.labels(),
+                reply
+                    .note
+                    .as_deref()
+                    .map_or("", |note| note.trim())
+                    .to_string(),
+                reply
+                    .msg
+                    .as_deref()
+                    .map_or("", |msg| msg.trim())
+                    .to_string(),
+            ];
+            let notes = notes
+                .into_iter()
+                .filter(|note| !note.is_empty())
+                .collect::<Vec<_>>();
+            match meals.is_empty() {
+                true => writeln!(out, "    {}: {}", guest.user(), notes.join("; ")),
+                false => writeln!(
+                    out,
+                    "    {} ({}): {}",
+                    guest.user(),
+                    meals.join(", "),
+                    notes.join("; ")
+                ),
+            }
+            .unwrap();
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn caterer_legacy_msg() {
+        // Reply from before dietary details, with only a message
+        let record: Record = serde_json::from_value(json!({
+            "group": 1,
+            "first": "Ann",
+            "last": "Lee",
+            "attend": "Yes",
+            "msg": "Severe nut allergy",
+        }))
+        .unwrap();
+        let db = Database::new(vec![Guest::from(record)]);
+        let report = caterer(&db);
+        assert!(report.contains("Ann Lee"));
+        assert!(report.contains("Severe nut allergy"));
+    }
+}
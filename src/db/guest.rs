@@ -1,9 +1,15 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{Debug, Display};
+use std::marker::PhantomData;
 use std::ops::Deref;
+use std::str::FromStr;
 
-use serde::{Deserialize, Serialize};
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::db::Group;
+use crate::db::event::Schedule;
+use crate::db::menu::Menu;
+use crate::db::{Group, Ident};
 use crate::user::User;
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -13,11 +19,32 @@ pub struct Guest {
     pub(super) user: User,
     #[serde(default)]
     pub(super) child: bool,
+    #[serde(default)]
+    pub(super) code: Option<String>,
+    #[serde(default)]
+    pub(super) email: Option<String>,
+    #[serde(default)]
+    pub(super) events: Events,
+    #[serde(default)]
+    pub(super) plus_one: bool,
+    #[serde(default)]
+    pub(super) host: Option<Ident>,
+    #[serde(default)]
+    pub(super) table: Option<String>,
     #[serde(flatten)]
     pub(super) reply: Reply,
 }
 
 impl Guest {
+    pub fn new(group: Group, user: User, child: bool) -> Self {
+        Self {
+            group,
+            user,
+            child,
+            ..Default::default()
+        }
+    }
+
     pub fn group(&self) -> Group {
         self.group
     }
@@ -30,6 +57,39 @@ impl Guest {
         self.child
     }
 
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref().filter(|code| !code.trim().is_empty())
+    }
+
+    pub fn email(&self) -> Option<&str> {
+        self.email
+            .as_deref()
+            .filter(|email| !email.trim().is_empty())
+    }
+
+    pub fn events(&self) -> &Events {
+        &self.events
+    }
+
+    pub fn invited(&self, event: &str) -> bool {
+        // Guests without any events listed are invited to all of them
+        self.events.is_empty() || self.events.contains(&event.to_string())
+    }
+
+    pub fn plus_one(&self) -> bool {
+        self.plus_one
+    }
+
+    pub fn host(&self) -> Option<&Ident> {
+        self.host.as_ref()
+    }
+
+    pub fn table(&self) -> Option<&str> {
+        self.table
+            .as_deref()
+            .filter(|table| !table.trim().is_empty())
+    }
+
     pub fn reply(&self) -> &Reply {
         &self.reply
     }
@@ -37,23 +97,98 @@ impl Guest {
     pub fn update(&mut self, reply: Reply) {
         self.reply = reply;
     }
+
+    pub fn edited(&self, group: Group, child: bool, events: Events, plus_one: bool) -> Self {
+        Self {
+            group,
+            child,
+            events,
+            plus_one,
+            ..self.clone()
+        }
+    }
 }
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Reply {
-    pub attend: Option<Attend>,
-    pub meal: Option<Meal>,
+    #[serde(default)]
+    pub events: BTreeMap<String, Answer>,
+    #[serde(default)]
+    pub diet: Diets,
+    pub note: Option<Message>,
     pub msg: Option<Message>,
 }
 
 impl Reply {
+    pub fn answer(&self, event: &str) -> Option<&Answer> {
+        self.events.get(event)
+    }
+
+    /// Sets an event's answer from a `<event>.<field>` key.
+    ///
+    /// Keys which don't name an event's field are ignored.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let Some((event, field)) = key.split_once('.') else {
+            return Ok(());
+        };
+        if value.is_empty() {
+            return Ok(());
+        }
+        match field {
+            "attend" => {
+                let attend = value.parse()?;
+                self.events.entry(event.to_string()).or_default().attend = Some(attend);
+            }
+            "meal" => {
+                let meal = Meal::from(value);
+                self.events.entry(event.to_string()).or_default().meal = Some(meal);
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    pub fn attending(&self) -> bool {
+        self.events
+            .values()
+            .any(|answer| matches!(answer.attend, Some(Attend::Yes)))
+    }
+
+    pub fn attends(&self, event: &str) -> bool {
+        self.answer(event)
+            .is_some_and(|answer| matches!(answer.attend, Some(Attend::Yes)))
+    }
+
     pub fn responded(&self) -> bool {
-        self.attend.is_some() || self.meal.is_some() || self.msg.is_some()
+        self.events
+            .values()
+            .any(|answer| answer.attend.is_some() || answer.meal.is_some())
+            || !self.diet.is_empty()
+            || self.note.is_some()
+            || self.msg.is_some()
     }
 
-    pub fn validate(&mut self) {
-        if !matches!(self.attend, Some(Attend::Yes)) {
-            self.meal = None;
+    pub fn validate(&mut self, guest: &Guest, menu: &Menu, schedule: &Schedule) {
+        // Only keep answers for events the guest is invited to
+        self.events.retain(|id, answer| {
+            let Some(event) = schedule.get(id).filter(|event| guest.invited(&event.id)) else {
+                return false;
+            };
+            if !event.meals || !matches!(answer.attend, Some(Attend::Yes)) {
+                answer.meal = None;
+            }
+            if let Some(meal) = &answer.meal {
+                if !menu.allows(meal, guest.child()) {
+                    answer.meal = None;
+                }
+            }
+            answer.attend.is_some() || answer.meal.is_some()
+        });
+        // Only keep dietary details for attending guests
+        if !self.attending() {
+            self.diet = Diets::default();
+            self.note = None;
         }
     }
 }
@@ -61,20 +196,40 @@ impl Reply {
 impl Display for Reply {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         Debug::fmt(
-            &[
-                self.attend.as_ref().map(ToString::to_string),
-                self.meal.as_ref().map(ToString::to_string),
-                self.msg.as_ref().map(ToString::to_string),
-            ]
-            .iter()
-            .flatten()
-            .collect::<Vec<_>>(),
+            &self
+                .events
+                .iter()
+                .map(|(event, answer)| Some(format!("{event}: {answer}")))
+                .chain([
+                    Some(self.diet.to_string()).filter(|diet| !diet.is_empty()),
+                    self.note.as_ref().map(ToString::to_string),
+                    self.msg.as_ref().map(ToString::to_string),
+                ])
+                .flatten()
+                .collect::<Vec<_>>(),
             f,
         )
     }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Answer {
+    pub attend: Option<Attend>,
+    pub meal: Option<Meal>,
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.attend, &self.meal) {
+            (Some(attend), Some(meal)) => write!(f, "{attend} ({meal})"),
+            (Some(attend), None) => write!(f, "{attend}"),
+            (None, Some(meal)) => write!(f, "({meal})"),
+            (None, None) => write!(f, "-"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum Attend {
     Yes,
     No,
@@ -86,32 +241,225 @@ impl Display for Attend {
     }
 }
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
-pub enum Meal {
-    #[default]
-    Chicken,
-    Fish,
-    Veggie,
-    Kids,
-    NoMeal,
+impl FromStr for Attend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Yes" => Ok(Attend::Yes),
+            "No" => Ok(Attend::No),
+            _ => Err(format!("unknown attendance: `{s}`")),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct Meal(String);
+
+impl From<&str> for Meal {
+    fn from(id: &str) -> Self {
+        Self(id.to_string())
+    }
+}
+
+impl Deref for Meal {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
 }
 
 impl Display for Meal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        Display::fmt(
-            match self {
-                Meal::Chicken => "Chicken",
-                Meal::Fish => "Fish",
-                Meal::Veggie => "Vegetarian",
-                Meal::Kids => "Kids Meal",
-                Meal::NoMeal => "No Meal",
-            },
-            f,
-        )
+        Display::fmt(&self.0, f)
     }
 }
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum Diet {
+    Vegetarian,
+    Vegan,
+    GlutenFree,
+    DairyFree,
+    NutAllergy,
+    Shellfish,
+    KosherStrict,
+    Halal,
+}
+
+impl Diet {
+    pub const ALL: [Diet; 8] = [
+        Diet::Vegetarian,
+        Diet::Vegan,
+        Diet::GlutenFree,
+        Diet::DairyFree,
+        Diet::NutAllergy,
+        Diet::Shellfish,
+        Diet::KosherStrict,
+        Diet::Halal,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Diet::Vegetarian => "Vegetarian",
+            Diet::Vegan => "Vegan",
+            Diet::GlutenFree => "Gluten-free",
+            Diet::DairyFree => "Dairy-free",
+            Diet::NutAllergy => "Nut allergy",
+            Diet::Shellfish => "Shellfish allergy",
+            Diet::KosherStrict => "Strictly kosher",
+            Diet::Halal => "Halal",
+        }
+    }
+}
+
+impl Display for Diet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+impl FromStr for Diet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Diet::ALL
+            .into_iter()
+            .find(|diet| diet.to_string() == s)
+            .ok_or_else(|| format!("unknown diet: `{s}`"))
+    }
+}
+
+pub type Diets = Set<Diet>;
+
+pub type Events = Set<String>;
+
+/// Set of values.
+///
+/// Stored as a single `;`-separated field, but also accepts a sequence of
+/// values (i.e. repeated form checkboxes).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Set<T: Ord>(BTreeSet<T>);
+
+impl<T: Ord> Set<T> {
+    pub fn contains(&self, value: &T) -> bool {
+        self.0.contains(value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Set<Diet> {
+    pub fn labels(&self) -> String {
+        self.iter()
+            .map(|diet| diet.label())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl<T: Ord> Default for Set<T> {
+    fn default() -> Self {
+        Self(BTreeSet::new())
+    }
+}
+
+impl<T: Ord> FromIterator<T> for Set<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl<T: Ord + Display> Display for Set<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ids = self.iter().map(ToString::to_string).collect::<Vec<_>>();
+        write!(f, "{}", ids.join(";"))
+    }
+}
+
+impl<T> FromStr for Set<T>
+where
+    T: Ord + FromStr,
+    T::Err: Display,
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(';')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(|id| id.parse().map_err(|err: T::Err| err.to_string()))
+            .collect()
+    }
+}
+
+impl<T: Ord + Display> Serialize for Set<T> {
+    fn serialize<S: Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        ser.collect_str(self)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Set<T>
+where
+    T: Ord + FromStr,
+    T::Err: Display,
+{
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        struct SetVisitor<T>(PhantomData<T>);
+
+        impl<'de, T> Visitor<'de> for SetVisitor<T>
+        where
+            T: Ord + FromStr,
+            T::Err: Display,
+        {
+            type Value = Set<T>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a `;`-separated string or a sequence of values")
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(Set::default())
+            }
+
+            fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(Set::default())
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                v.parse().map_err(E::custom)
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                self.visit_str(&v.to_string())
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                self.visit_str(&v.to_string())
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut set = BTreeSet::new();
+                while let Some(id) = seq.next_element::<String>()? {
+                    set.extend(id.parse::<Set<T>>().map_err(de::Error::custom)?.0);
+                }
+                Ok(Set(set))
+            }
+        }
+
+        de.deserialize_any(SetVisitor(PhantomData))
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Message(String);
 
 impl Deref for Message {
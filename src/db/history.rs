@@ -0,0 +1,53 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use axum_login::AuthUser;
+
+use super::guest::Reply;
+use super::Ident;
+use crate::user::{Role, User};
+
+/// A change to a guest's reply.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Revision {
+    pub time: DateTime<Utc>,
+    pub by: String,
+    /// Login identifier of the user, as names need not be unique.
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub admin: bool,
+    pub target: Ident,
+    pub old: Reply,
+    pub new: Reply,
+    /// Companion brought by the guest, before and after the change.
+    #[serde(default)]
+    pub old_companion: Option<String>,
+    #[serde(default)]
+    pub new_companion: Option<String>,
+}
+
+impl Revision {
+    pub fn new(
+        by: &User,
+        target: Ident,
+        (old, old_companion): (Reply, Option<String>),
+        (new, new_companion): (Reply, Option<String>),
+    ) -> Self {
+        Self {
+            time: Utc::now(),
+            by: by.name(),
+            id: by.get_id(),
+            admin: by.role == Role::Admin,
+            target,
+            old,
+            new,
+            old_companion,
+            new_companion,
+        }
+    }
+
+    pub fn when(&self) -> String {
+        self.time.format("%Y-%m-%d %H:%M UTC").to_string()
+    }
+}
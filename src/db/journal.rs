@@ -0,0 +1,67 @@
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+
+use log::{debug, error};
+use tokio::sync::oneshot;
+use tokio::task::{self, JoinHandle};
+
+use super::history::Revision;
+use super::store::{Pending, Store};
+use super::Error;
+
+/// Acknowledgement of a journal job, once it has completed.
+pub type Ack = oneshot::Receiver<Result<(), Error>>;
+
+enum Job {
+    Append(Vec<Pending>, Vec<Revision>),
+    Seal,
+}
+
+/// Handle to the thread journaling changes off the async runtime.
+#[derive(Clone, Debug)]
+pub struct Journal {
+    tx: Sender<(Job, oneshot::Sender<Result<(), Error>>)>,
+}
+
+impl Journal {
+    pub fn spawn(store: Arc<dyn Store>) -> (Self, JoinHandle<()>) {
+        let (tx, rx) = mpsc::channel::<(Job, oneshot::Sender<_>)>();
+        let task = task::spawn_blocking(move || {
+            // Run the jobs in order, so a seal covers all changes journaled before it
+            for (job, ack) in rx {
+                let res = match job {
+                    Job::Append(pending, revisions) => store.journal(&pending, &revisions),
+                    Job::Seal => store.seal(),
+                };
+                // Log errors nobody is waiting on
+                if let Err(Err(err)) = ack.send(res) {
+                    error!("journal: {err}");
+                }
+            }
+            debug!("journal: closed");
+        });
+
+        (Self { tx }, task)
+    }
+
+    pub fn append(&self, pending: Vec<Pending>, revisions: Vec<Revision>) -> Ack {
+        self.send(Job::Append(pending, revisions))
+    }
+
+    pub fn seal(&self) -> Ack {
+        self.send(Job::Seal)
+    }
+
+    fn send(&self, job: Job) -> Ack {
+        let (tx, rx) = oneshot::channel();
+        // Once closed, the job is dropped along with its acknowledgement
+        let _ = self.tx.send((job, tx));
+        rx
+    }
+}
+
+/// Waits for a journal job to complete.
+pub async fn wait(ack: Ack) -> Result<(), Error> {
+    ack.await
+        .unwrap_or_else(|err| Err(Error::Flush(err.to_string())))
+}
@@ -0,0 +1,85 @@
+use serde::Deserialize;
+
+use super::guest::Meal;
+
+#[derive(Clone, Debug)]
+pub struct Menu {
+    choices: Vec<Choice>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Choice {
+    pub id: Meal,
+    pub label: String,
+    #[serde(default)]
+    pub guests: Audience,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Audience {
+    #[default]
+    All,
+    Adults,
+    Children,
+}
+
+impl Audience {
+    pub fn serves(self, child: bool) -> bool {
+        match self {
+            Audience::All => true,
+            Audience::Adults => !child,
+            Audience::Children => child,
+        }
+    }
+}
+
+impl Menu {
+    pub fn new(choices: Vec<Choice>) -> Self {
+        // Fall back to the default menu when none is configured
+        if choices.is_empty() {
+            return Self::default();
+        }
+        Self { choices }
+    }
+
+    pub fn choices(&self) -> &[Choice] {
+        &self.choices
+    }
+
+    pub fn options(&self, child: bool) -> impl Iterator<Item = &Choice> {
+        self.choices
+            .iter()
+            .filter(move |choice| choice.guests.serves(child))
+    }
+
+    pub fn allows(&self, meal: &Meal, child: bool) -> bool {
+        self.options(child).any(|choice| choice.id == *meal)
+    }
+
+    pub fn label<'a>(&'a self, meal: &'a Meal) -> &'a str {
+        self.choices
+            .iter()
+            .find(|choice| choice.id == *meal)
+            .map_or(meal, |choice| &choice.label)
+    }
+}
+
+impl Default for Menu {
+    fn default() -> Self {
+        let choice = |id: &str, label: &str, guests| Choice {
+            id: Meal::from(id),
+            label: label.to_string(),
+            guests,
+        };
+        Self {
+            choices: vec![
+                choice("Chicken", "Chicken", Audience::Adults),
+                choice("Fish", "Fish", Audience::Adults),
+                choice("Veggie", "Vegetarian", Audience::Adults),
+                choice("Kids", "Kids Meal", Audience::Children),
+                choice("NoMeal", "No Meal", Audience::All),
+            ],
+        }
+    }
+}
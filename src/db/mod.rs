@@ -1,25 +1,52 @@
+use std::collections::HashSet;
 use std::fmt::Display;
+use std::future::Future;
 use std::io;
 use std::ops::Deref;
-use std::path::{Path, PathBuf};
+use std::path::Path;
+use std::sync::Arc;
 
 use indexmap::IndexMap;
-use log::{debug, info, trace};
-use serde::Deserialize;
+use log::{debug, info, trace, warn};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use tokio::sync::mpsc::UnboundedSender;
 use uuid::Uuid;
 
-use crate::user::User;
+use crate::mail::notify::{Change, Notifier};
+use crate::metrics;
+use crate::user::{self, User};
 
+pub mod check;
+pub mod deadline;
+pub mod event;
+pub mod export;
 pub mod guest;
+pub mod history;
+pub mod journal;
+pub mod menu;
+pub mod plan;
 mod record;
+pub mod seating;
+pub mod stats;
+pub mod store;
+pub mod writer;
 
-use self::guest::{Guest, Reply};
-use self::record::Record;
+use self::check::Report;
+use self::deadline::Deadline;
+use self::event::Schedule;
+use self::guest::{Events, Guest, Reply};
+use self::history::Revision;
+use self::journal::{Ack, Journal};
+use self::menu::Menu;
+use self::seating::{Chart, Tables};
+use self::stats::Stats;
+use self::store::{Csv, Lines, Pending, Store};
+use self::writer::Status;
 
 pub type Group = usize;
 
-#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct Ident(Uuid);
 
 impl Default for Ident {
@@ -28,6 +55,12 @@ impl Default for Ident {
     }
 }
 
+impl From<Uuid> for Ident {
+    fn from(uuid: Uuid) -> Self {
+        Self(uuid)
+    }
+}
+
 impl Deref for Ident {
     type Target = Uuid;
 
@@ -44,11 +77,25 @@ impl Display for Ident {
 
 #[derive(Debug, Default)]
 pub struct Database {
-    pub path: Option<PathBuf>,
-    pub locked: bool,
+    pub store: Option<Arc<dyn Store>>,
+    pub lock: bool,
+    pub deadline: Option<Deadline>,
+    pub require_code: bool,
+    pub admins: Vec<User>,
+    pub menu: Menu,
+    pub schedule: Schedule,
+    pub tables: Tables,
+    pub notifier: Option<Notifier>,
+    tx: Option<UnboundedSender<Ident>>,
+    journal: Option<Journal>,
+    acks: Vec<Ack>,
+    status: Status,
+    history: Vec<Revision>,
+    edited: IndexMap<Ident, User>,
     idents: IndexMap<User, Ident>,
     guests: IndexMap<Ident, Guest>,
     groups: IndexMap<Group, Vec<Ident>>,
+    codes: IndexMap<String, Group>,
 }
 
 impl Database {
@@ -65,6 +112,15 @@ impl Database {
             // Add the user's identifier to the database
             let user = guest.user().clone();
             db.idents.insert(user, ident);
+            // Add the group's invite code (if any)
+            if let Some(code) = guest.code() {
+                match db.codes.insert(user::sanitize(code), group) {
+                    Some(other) if other != group => {
+                        warn!("duplicate code: groups {other} and {group}");
+                    }
+                    _ => (),
+                }
+            }
             // Add the guest into the database
             db.guests.insert(ident, guest);
             // Insert the guest (by identifier) into their group
@@ -88,13 +144,62 @@ impl Database {
         self.guests.len()
     }
 
-    pub fn auth(&self, user: &User) -> Option<&User> {
-        self.idents
+    pub fn auth(&self, user: &User, code: Option<&str>) -> Option<&User> {
+        // Look up the guest by name
+        let guest = self
+            .idents
             .get(user)
+            .and_then(|ident| self.guests.get(ident))?;
+        // Check the invite code if provided or required
+        let code = code.filter(|code| !code.trim().is_empty());
+        if code.is_some() || self.require_code {
+            let group = self.codes.get(&user::sanitize(code?))?;
+            if *group != guest.group() {
+                return None;
+            }
+        }
+        Some(guest.user())
+    }
+
+    pub fn redeem(&self, code: &str) -> Option<&User> {
+        // Look up the group by invite code
+        let group = self.codes.get(&user::sanitize(code))?;
+        // Use the group's first guest
+        self.groups
+            .get(group)
+            .and_then(|idents| idents.first())
             .and_then(|ident| self.guests.get(ident))
             .map(Guest::user)
     }
 
+    pub fn admin(&self, name: &str, password: &str) -> Option<&User> {
+        // Look up the admin by name
+        let name = user::sanitize(name);
+        let admin = self
+            .admins
+            .iter()
+            .find(|admin| user::sanitize(&admin.name()) == name)?;
+        // Verify the password
+        admin.verify(password).then_some(admin)
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats::from(self)
+    }
+
+    pub fn chart(&self) -> Chart {
+        Chart::from(self)
+    }
+
+    pub fn has_codes(&self) -> bool {
+        self.require_code || !self.codes.is_empty()
+    }
+
+    pub fn locked(&self) -> bool {
+        // Lock once the deadline passes, even without a restart
+        self.lock || self.deadline.is_some_and(|deadline| deadline.passed())
+    }
+
     #[allow(unused)]
     pub fn ident(&self, user: &User) -> Option<&Ident> {
         self.idents.get(user)
@@ -114,68 +219,644 @@ impl Database {
             .ok_or(Error::Guest)
     }
 
-    pub fn update(&mut self, ident: &Ident, reply: Reply) -> Result<(), Error> {
+    pub fn groups(&self) -> impl Iterator<Item = (Group, &[Ident])> {
+        self.groups
+            .iter()
+            .map(|(group, idents)| (*group, idents.as_slice()))
+    }
+
+    pub fn update(
+        &mut self,
+        ident: &Ident,
+        reply: Reply,
+        companion: Option<User>,
+        by: &User,
+    ) -> Result<(), Error> {
         // Error when locked
-        if self.locked {
+        if self.locked() {
             return Err(Error::Locked);
         }
+        // Perform the update
+        let revision = self.revise(ident, reply, companion, by)?;
+        // Notify the couple of any change (if configured)
+        if let (Some(notifier), Some(revision)) = (&self.notifier, revision) {
+            let guest = self.guests.get(ident).ok_or(Error::Guest)?;
+            let change = Change::new(
+                guest.clone(),
+                revision,
+                self.menu.clone(),
+                self.schedule.clone(),
+            );
+            notifier.send(change);
+        }
+
+        Ok(())
+    }
+
+    pub fn amend(&mut self, ident: &Ident, reply: Reply, by: &User) -> Result<(), Error> {
+        self.revise(ident, reply, None, by).map(drop)
+    }
+
+    fn revise(
+        &mut self,
+        ident: &Ident,
+        reply: Reply,
+        companion: Option<User>,
+        by: &User,
+    ) -> Result<Option<Revision>, Error> {
+        let name = |guest: &Guest| guest.user().name();
+        let old_companion = self.companion(ident).map(name);
+        let mut idents = vec![*ident];
+        // Bring a companion (if named) to attend with the guest
+        if let Some(user) = companion.filter(|_| reply.attending()) {
+            idents.push(self.bring(ident, user)?);
+        }
         // Extract the guest to update
         let guest = self.guests.get_mut(ident).ok_or(Error::Guest)?;
-        info!("update: `{}` -> {reply}", guest.user(),);
+        info!("update: `{}` -> {reply}, by: `{by}`", guest.user());
         // Perform the update
+        let old = guest.reply.clone();
         guest.update(reply);
+        // Drop any companion once the guest isn't attending
+        if !guest.reply.attending() {
+            if let Some(companion) = self.companion(ident).map(|guest| guest.user.ident) {
+                idents.extend(self.unlink(&companion)?.1);
+            }
+        }
+        // Record the change in the history, along with any companion brought or dropped
+        let guest = self.guests.get(ident).ok_or(Error::Guest)?;
+        let new_companion = self.companion(ident).map(name);
+        let revision = (guest.reply != old || new_companion != old_companion).then(|| {
+            let new = guest.reply.clone();
+            Revision::new(by, *ident, (old, old_companion), (new, new_companion))
+        });
+        self.history.extend(revision.iter().cloned());
+        // Notify the writer (if any), journaling the history with the changes
+        self.notify_all(&idents, revision.as_slice())?;
 
-        Ok(())
+        Ok(revision)
     }
 
-    pub fn write(&self) -> Result<(), Error> {
-        // Open the output file
-        let path = self.path.as_ref().ok_or(Error::Path)?;
-        let mut writer = csv::Writer::from_path(path)?;
-        debug!("writing: `{}`", path.display());
-        // Write the database
-        for guest in self.guests.values() {
-            // Convert the guest into a writable record
-            let record = Record::from(guest.clone());
-            // Serialize and write it
-            writer.serialize(record).map_err(Error::Csv)?;
-            trace!("wrote: `{}`, reply: {}", guest.user(), guest.reply());
+    pub fn history(&self, ident: &Ident) -> impl Iterator<Item = (usize, &Revision)> {
+        let ident = *ident;
+        self.history
+            .iter()
+            .enumerate()
+            .filter(move |(_, revision)| revision.target == ident)
+    }
+
+    pub fn revision(&self, idx: usize) -> Option<&Revision> {
+        self.history.get(idx)
+    }
+
+    pub fn edit(
+        &mut self,
+        ident: &Ident,
+        group: Group,
+        child: bool,
+        events: Events,
+        plus_one: bool,
+    ) -> Result<(), Error> {
+        // Extract the guest to edit
+        let guest = self.guests.get(ident).ok_or(Error::Guest)?;
+        let prev = guest.group();
+        // Move the guest between groups
+        if prev != group {
+            info!("edit: `{}`, group: {prev} -> {group}", guest.user());
+            self.ungroup(ident, prev);
+            let code = self.code(group);
+            self.groups.entry(group).or_default().push(*ident);
+            let guest = self.guests.get_mut(ident).ok_or(Error::Guest)?;
+            guest.group = group;
+            guest.code = code;
+        }
+        // Update the guest's details
+        let guest = self.guests.get_mut(ident).ok_or(Error::Guest)?;
+        if guest.child != child {
+            info!(
+                "edit: `{}`, child: {} -> {child}",
+                guest.user(),
+                guest.child
+            );
+            guest.child = child;
+        }
+        if guest.events != events {
+            info!(
+                "edit: `{}`, events: {} -> {events}",
+                guest.user(),
+                guest.events
+            );
+            guest.events = events;
+        }
+        if guest.plus_one != plus_one {
+            info!(
+                "edit: `{}`, plus-one: {} -> {plus_one}",
+                guest.user(),
+                guest.plus_one
+            );
+            guest.plus_one = plus_one;
+        }
+        self.track(ident);
+        // Notify the writer (if any)
+        self.notify(ident)
+    }
+
+    pub fn seat(&mut self, ident: &Ident, table: Option<String>) -> Result<(), Error> {
+        // Extract the guest to seat
+        let guest = self.guests.get_mut(ident).ok_or(Error::Guest)?;
+        let table = table.filter(|table| !table.trim().is_empty());
+        if guest.table == table {
+            return Ok(());
+        }
+        info!(
+            "seat: `{}`, table: {} -> {}",
+            guest.user(),
+            guest.table.as_deref().unwrap_or("none"),
+            table.as_deref().unwrap_or("none")
+        );
+        guest.table = table;
+        self.track(ident);
+        // Notify the writer (if any)
+        self.notify(ident)
+    }
+
+    pub fn insert(&mut self, guest: Guest) -> Result<Ident, Error> {
+        let ident = self.admit(guest)?;
+        // Notify the writer (if any)
+        self.notify(&ident)?;
+
+        Ok(ident)
+    }
+
+    fn admit(&mut self, mut guest: Guest) -> Result<Ident, Error> {
+        // Error on duplicate names
+        if self.idents.contains_key(guest.user()) {
+            return Err(Error::Duplicate);
+        }
+        info!("insert: `{}`, group: {}", guest.user(), guest.group());
+        // Share the group's invite code (if any)
+        let ident = guest.user().ident;
+        let group = guest.group();
+        guest.code = self.code(group);
+        // Add the guest into the database
+        self.idents.insert(guest.user().clone(), ident);
+        self.guests.insert(ident, guest);
+        self.groups.entry(group).or_default().push(ident);
+        self.track(&ident);
+
+        Ok(ident)
+    }
+
+    pub fn companion(&self, host: &Ident) -> Option<&Guest> {
+        self.guests
+            .values()
+            .find(|guest| guest.host.as_ref() == Some(host))
+    }
+
+    fn bring(&mut self, host: &Ident, user: User) -> Result<Ident, Error> {
+        // Error when locked
+        if self.locked() {
+            return Err(Error::Locked);
+        }
+        // Extract the guest bringing a companion
+        let guest = self.guests.get(host).ok_or(Error::Guest)?;
+        // Error unless the guest has an unused plus-one
+        if !guest.plus_one || self.companion(host).is_some() {
+            return Err(Error::PlusOne);
+        }
+        info!("bring: `{user}`, guest of: `{}`", guest.user());
+        // Invite the companion to the same events as the guest
+        let companion = Guest {
+            group: guest.group,
+            user,
+            events: guest.events.clone(),
+            host: Some(*host),
+            ..Default::default()
+        };
+        self.admit(companion)
+    }
+
+    pub fn remove(&mut self, ident: &Ident) -> Result<Guest, Error> {
+        let (guest, idents) = self.unlink(ident)?;
+        // Notify the writer (if any) of the removed and shifted guests
+        self.notify_all(&idents, &[])?;
+
+        Ok(guest)
+    }
+
+    fn unlink(&mut self, ident: &Ident) -> Result<(Guest, Vec<Ident>), Error> {
+        // Remove the guest from the database
+        let (idx, _, guest) = self.guests.shift_remove_full(ident).ok_or(Error::Guest)?;
+        info!("remove: `{}`, group: {}", guest.user(), guest.group());
+        self.idents.shift_remove(guest.user());
+        self.ungroup(ident, guest.group());
+        self.edited.insert(*ident, guest.user.clone());
+        // Collect the removed and shifted guests
+        let mut idents: Vec<_> = std::iter::once(*ident)
+            .chain(self.guests.keys().skip(idx).copied())
+            .collect();
+        // Remove any companion along with their host
+        if let Some(companion) = self.companion(ident).map(|guest| guest.user.ident) {
+            idents.extend(self.unlink(&companion)?.1);
+        }
+
+        Ok((guest, idents))
+    }
+
+    fn ungroup(&mut self, ident: &Ident, group: Group) {
+        // Remove the guest from their group
+        let Some(idents) = self.groups.get_mut(&group) else {
+            return;
+        };
+        idents.retain(|other| other != ident);
+        // Remove the group once empty, along with its invite code
+        if idents.is_empty() {
+            self.groups.shift_remove(&group);
+            self.codes.retain(|_, other| *other != group);
+        }
+    }
+
+    fn track(&mut self, ident: &Ident) {
+        // Track guests changed since the guestlist was loaded
+        if let Some(guest) = self.guests.get(ident) {
+            self.edited.insert(*ident, guest.user.clone());
+        }
+    }
+
+    fn code(&self, group: Group) -> Option<String> {
+        // Use the invite code of the group's guests
+        self.groups
+            .get(&group)
+            .and_then(|idents| idents.first())
+            .and_then(|ident| self.guests.get(ident))
+            .and_then(|guest| guest.code.clone())
+    }
+
+    fn notify(&mut self, ident: &Ident) -> Result<(), Error> {
+        self.notify_all(std::slice::from_ref(ident), &[])
+    }
+
+    fn notify_all(&mut self, idents: &[Ident], revisions: &[Revision]) -> Result<(), Error> {
+        // Notify the writer (if any)
+        if let Some(tx) = &self.tx {
+            for ident in idents {
+                tx.send(*ident)
+                    .map_err(|err| Error::Flush(err.to_string()))?;
+            }
+        }
+        // Journal the changes (if stored), so they survive until written
+        if self.store.is_none() {
+            return Ok(());
+        }
+        let pending: Vec<_> = idents
+            .iter()
+            .map(|ident| match self.guests.get(ident) {
+                Some(guest) => Pending::Update(Box::new(guest.clone())),
+                None => Pending::Remove(*ident),
+            })
+            .collect();
+        if let Some(journal) = &self.journal {
+            // Leave the I/O to the journal thread, awaited through `synced`
+            self.acks.push(journal.append(pending, revisions.to_vec()));
+        } else if let Some(store) = &self.store {
+            store.journal(&pending, revisions)?;
         }
 
         Ok(())
     }
-}
 
-impl TryFrom<&Path> for Database {
-    type Error = Error;
-
-    fn try_from(path: &Path) -> Result<Self, Self::Error> {
-        // Open the input file
-        let mut reader = csv::Reader::from_path(path)?;
-        // Read the guests
-        debug!("reading: `{}`", path.display());
-        let data: Vec<Guest> = reader
-            .deserialize::<Record>()
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(Error::Csv)?
+    /// Waits for the changes made so far to be journaled.
+    ///
+    /// Call this after releasing the lock on the database.
+    pub fn synced(&mut self) -> impl Future<Output = Result<(), Error>> {
+        let acks = std::mem::take(&mut self.acks);
+        async move {
+            for ack in acks {
+                journal::wait(ack).await?;
+            }
+            Ok(())
+        }
+    }
+
+    pub fn status(&self) -> &Status {
+        &self.status
+    }
+
+    pub fn close(&mut self) {
+        // Disconnect the writer, flushing any pending updates
+        self.tx = None;
+        self.journal = None;
+        // Disconnect the notifier, sending any pending messages
+        self.notifier = None;
+    }
+
+    pub fn recover(&mut self) -> Result<usize, Error> {
+        // Replay any pending updates
+        let count = self.replay()?;
+        // Save the recovered database
+        if count > 0 {
+            self.write()?;
+        }
+
+        Ok(count)
+    }
+
+    pub fn replay(&mut self) -> Result<usize, Error> {
+        // Read any pending updates from the store, leaving it untouched
+        let Some(store) = &self.store else {
+            return Ok(0);
+        };
+        // Read the history of revisions
+        self.history = store.history()?;
+        let pending = store.pending()?;
+        if pending.is_empty() {
+            return Ok(0);
+        }
+        // Replay them over the database
+        let mut guests = std::mem::take(&mut self.guests);
+        for change in &pending {
+            match change {
+                Pending::Update(guest) => {
+                    // Match the guest by identifier, falling back to name
+                    let mut guest = *guest.clone();
+                    if !guests.contains_key(&guest.user.ident) {
+                        if let Some(ident) = self.idents.get(&guest.user) {
+                            guest.user.ident = *ident;
+                        }
+                    }
+                    info!("replay: `{}` -> {}", guest.user(), guest.reply());
+                    self.edited.insert(guest.user.ident, guest.user.clone());
+                    guests.insert(guest.user.ident, guest);
+                }
+                Pending::Remove(ident) => {
+                    if let Some(guest) = guests.shift_remove(ident) {
+                        info!("replay: removed: `{}`", guest.user());
+                        self.edited.insert(*ident, guest.user);
+                    }
+                }
+            }
+        }
+        self.reindex(guests.into_values().collect());
+
+        Ok(pending.len())
+    }
+
+    pub fn reload(&mut self, guests: Vec<Guest>) -> Result<Changes, Error> {
+        // Refuse to undo changes made since the guestlist was loaded
+        let conflicts = self.conflicts(&guests);
+        if !conflicts.is_empty() {
+            return Err(Error::Conflict(conflicts.join(", ")));
+        }
+        let mut changes = Changes::default();
+        // Merge the guests with those already in the database
+        let mut kept = HashSet::new();
+        let mut merged = Vec::with_capacity(guests.len());
+        for mut guest in guests {
+            // Match the guest by identifier, falling back to name
+            let prev = self.guests.get(&guest.user.ident).or_else(|| {
+                self.idents
+                    .get(&guest.user)
+                    .and_then(|ident| self.guests.get(ident))
+            });
+            match prev {
+                Some(prev) => {
+                    // Keep the guest's identifier
+                    guest.user.ident = prev.user.ident;
+                    // Keep any reply already recorded
+                    if prev.reply.responded() {
+                        guest.reply = prev.reply.clone();
+                    }
+                    // Keep any seat already assigned
+                    if guest.table.is_none() {
+                        guest.table = prev.table.clone();
+                    }
+                    // Check for changed details
+                    if prev.user.name() != guest.user.name()
+                        || prev.group != guest.group
+                        || prev.child != guest.child
+                        || prev.code != guest.code
+                        || prev.email != guest.email
+                        || prev.events != guest.events
+                        || prev.plus_one != guest.plus_one
+                        || prev.table != guest.table
+                    {
+                        info!("reload: changed: `{}`", guest.user());
+                        changes.changed.push(guest.user.name());
+                    }
+                    kept.insert(guest.user.ident);
+                }
+                None => {
+                    info!("reload: added: `{}`", guest.user());
+                    changes.added.push(guest.user.name());
+                }
+            }
+            merged.push(guest);
+        }
+        // Keep companions of guests still in the guestlist
+        for (ident, guest) in &self.guests {
+            let Some(group) = guest.host.and_then(|host| {
+                merged
+                    .iter()
+                    .find(|other| other.user.ident == host && kept.contains(&host))
+                    .map(Guest::group)
+            }) else {
+                continue;
+            };
+            if kept.insert(*ident) {
+                info!("reload: kept: `{}`", guest.user());
+                let mut guest = guest.clone();
+                guest.group = group;
+                merged.push(guest);
+            }
+        }
+        // Find guests no longer in the guestlist
+        let removed: Vec<_> = self
+            .guests
+            .iter()
+            .filter(|(ident, _)| !kept.contains(*ident))
+            .map(|(ident, guest)| {
+                info!("reload: removed: `{}`", guest.user());
+                changes.removed.push(guest.user.name());
+                *ident
+            })
+            .collect();
+        self.reindex(merged);
+        self.edited.clear();
+        // Notify the writer (if any) of every guest
+        let idents: Vec<_> = removed.iter().chain(self.guests.keys()).copied().collect();
+        self.notify_all(&idents, &[])?;
+
+        Ok(changes)
+    }
+
+    pub fn diverge(&mut self, guests: &[Guest]) -> usize {
+        // Consider every guest in the database or guestlist
+        let candidates: Vec<_> = self
+            .guests
+            .iter()
+            .map(|(ident, guest)| (*ident, guest.user.clone()))
+            .chain(
+                guests
+                    .iter()
+                    .filter(|guest| !self.idents.contains_key(&guest.user))
+                    .map(|guest| (guest.user.ident, guest.user.clone())),
+            )
+            .collect();
+        // Track those which differ from the guestlist as changed here,
+        // as changes from before a restore are otherwise unknown
+        self.edited = candidates
             .into_iter()
-            .map(Into::into)
+            .filter(|(ident, user)| self.conflict(ident, user, guests).is_some())
             .collect();
-        // Construct a database
-        Ok(Database::new(data))
+
+        self.edited.len()
+    }
+
+    fn conflicts(&self, guests: &[Guest]) -> Vec<String> {
+        // Check each guest changed here against the guestlist
+        self.edited
+            .iter()
+            .filter_map(|(ident, user)| {
+                let conflict = self.conflict(ident, user, guests)?;
+                Some(format!("{conflict} `{user}`"))
+            })
+            .collect()
+    }
+
+    fn conflict(&self, ident: &Ident, user: &User, guests: &[Guest]) -> Option<&'static str> {
+        let listed = guests
+            .iter()
+            .find(|guest| guest.user.ident == *ident || guest.user == *user);
+        match (self.guests.get(ident), listed) {
+            // Companions are kept along with their host
+            (Some(guest), None) if guest.host.is_none() => Some("added"),
+            (None, Some(_)) => Some("removed"),
+            (Some(guest), Some(listed))
+                if guest.group != listed.group
+                    || guest.child != listed.child
+                    || guest.events != listed.events
+                    || guest.plus_one != listed.plus_one
+                    || listed.table.is_some() && guest.table != listed.table =>
+            {
+                Some("edited")
+            }
+            _ => None,
+        }
+    }
+
+    fn reindex(&mut self, guests: Vec<Guest>) {
+        // Rebuild the indexes
+        let Database {
+            idents,
+            guests,
+            groups,
+            codes,
+            ..
+        } = Database::new(guests);
+        self.idents = idents;
+        self.guests = guests;
+        self.groups = groups;
+        self.codes = codes;
+    }
+
+    pub fn write(&self) -> Result<(), Error> {
+        // Write the database to the store
+        let store = self.store.as_ref().ok_or(Error::Store)?;
+        metrics::write(|| {
+            // Seal the journal, as this writes all of its changes
+            store.seal()?;
+            store.write(&self.guests.values().cloned().collect::<Vec<_>>())
+        })
+    }
+}
+
+pub fn read(path: &Path, schedule: &Schedule) -> Result<Lines, Error> {
+    // Read the guests without modifying the guestlist
+    let mut lines = Csv::new(path.to_path_buf(), schedule.ids()).lines()?;
+    migrate(&mut lines.entries, schedule);
+
+    Ok(lines)
+}
+
+pub fn load(path: &Path, menu: &Menu, schedule: &Schedule) -> Result<Vec<Guest>, Error> {
+    // Read the guests, which must already be identified
+    let Lines { entries, missing } = read(path, schedule)?;
+    if !missing.is_empty() {
+        return Err(Error::Unidentified(missing.len()));
+    }
+    // Validate the guests
+    let report = Report::new(&entries, menu, schedule);
+    report.log();
+    if report.errors() > 0 {
+        return Err(Error::Invalid(report.errors()));
+    }
+
+    Ok(entries.into_iter().map(|(_, guest)| guest).collect())
+}
+
+pub fn assign(path: &Path) -> Result<usize, Error> {
+    // Write identifiers into the guestlist, keeping all its columns
+    Csv::new(path.to_path_buf(), Vec::default()).assign()
+}
+
+fn migrate(entries: &mut [(u64, Guest)], schedule: &Schedule) {
+    // Answer the schedule's events with any replies from before it
+    for (_, guest) in entries {
+        schedule.migrate(guest);
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Changes {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl Display for Changes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} added, {} removed, {} changed",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len()
+        )
     }
 }
 
 #[derive(Debug, Error)]
 pub enum Error {
-    #[error("missing path")]
-    Path,
+    #[error("missing store")]
+    Store,
     #[error(transparent)]
     Io(#[from] io::Error),
     #[error(transparent)]
     Csv(#[from] csv::Error),
+    #[error(transparent)]
+    Sql(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Uuid(#[from] uuid::Error),
+    #[error(transparent)]
+    Xlsx(#[from] rust_xlsxwriter::XlsxError),
     #[error("missing guest")]
     Guest,
+    #[error("duplicate guest")]
+    Duplicate,
+    #[error("no plus-one available")]
+    PlusOne,
+    #[error("invalid guestlist: {0} errors")]
+    Invalid(usize),
+    #[error("guestlist is missing {0} ids, assign them with `check --assign-ids`")]
+    Unidentified(usize),
+    #[error("invalid reply: {0}")]
+    Reply(String),
+    #[error("guestlist is missing changes made since loading: {0}")]
+    Conflict(String),
     #[error("database locked")]
     Locked,
+    #[error("failed to flush: {0}")]
+    Flush(String),
 }
@@ -0,0 +1,475 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+
+use indexmap::IndexMap;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use super::guest::Guest;
+use super::seating::Table;
+use super::{Database, Group, Ident};
+use crate::user::User;
+
+/// Penalty for each guest seated beyond a table's capacity.
+const CROWDED: usize = 100;
+
+/// Penalty for each pair of groups to keep apart that share a table.
+const APART: usize = 50;
+
+/// Penalty for each child seated away from their group's adults.
+const STRAYED: usize = 20;
+
+/// Penalty for each pair of groups to keep together that don't share a table.
+const TOGETHER: usize = 10;
+
+/// Penalty for each extra table a group is split across.
+const SPLIT: usize = 10;
+
+/// Penalty for each guest seated at a table for the other side.
+const SIDE: usize = 1;
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Constraints {
+    /// Groups that must sit with each other.
+    #[serde(default)]
+    pub together: Vec<Vec<Group>>,
+    /// Groups that must not sit with each other.
+    #[serde(default)]
+    pub apart: Vec<Vec<Group>>,
+    /// Groups belonging to each side, e.g. the bride's or groom's.
+    #[serde(default)]
+    pub sides: BTreeMap<String, Vec<Group>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Seat {
+    pub id: Ident,
+    pub group: Group,
+    pub first: String,
+    pub last: String,
+    pub table: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Plan<'a> {
+    pub seats: Vec<Seat>,
+    pub score: usize,
+    pub violations: Vec<Violation<'a>>,
+}
+
+#[derive(Debug)]
+pub enum Violation<'a> {
+    Crowded(&'a str, usize, usize),
+    Apart(Group, Group, &'a str),
+    Strayed(&'a User, &'a str),
+    Together(Group, Group),
+    Split(Group, usize),
+    Side(&'a User, &'a str, &'a str),
+}
+
+impl Violation<'_> {
+    pub fn penalty(&self) -> usize {
+        match self {
+            Violation::Crowded(_, seated, capacity) => CROWDED * (seated - capacity),
+            Violation::Apart(..) => APART,
+            Violation::Strayed(..) => STRAYED,
+            Violation::Together(..) => TOGETHER,
+            Violation::Split(_, tables) => SPLIT * (tables - 1),
+            Violation::Side(..) => SIDE,
+        }
+    }
+}
+
+impl Display for Violation<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::Crowded(table, seated, capacity) => {
+                write!(f, "{table} is over capacity: {seated} / {capacity}")
+            }
+            Violation::Apart(a, b, table) => {
+                write!(
+                    f,
+                    "groups {a} and {b} must not sit together, but share {table}"
+                )
+            }
+            Violation::Strayed(name, table) => {
+                write!(f, "{name} is seated away from their group at {table}")
+            }
+            Violation::Together(a, b) => {
+                write!(
+                    f,
+                    "groups {a} and {b} must sit together, but don't share a table"
+                )
+            }
+            Violation::Split(group, tables) => {
+                write!(f, "group {group} is split across {tables} tables")
+            }
+            Violation::Side(name, side, table) => {
+                write!(f, "{name} is on the {side} side, but seated at {table}")
+            }
+        }
+    }
+}
+
+/// Guests seated together as a unit, usually a whole group.
+#[derive(Debug)]
+struct Party<'a> {
+    guests: Vec<&'a Guest>,
+}
+
+struct Solver<'a> {
+    tables: &'a [Table],
+    constraints: &'a Constraints,
+    parties: Vec<Party<'a>>,
+    sides: IndexMap<Group, &'a str>,
+}
+
+impl<'a> Solver<'a> {
+    fn new(guests: Vec<&'a Guest>, tables: &'a [Table], constraints: &'a Constraints) -> Self {
+        let largest = tables.iter().map(|table| table.capacity).max().unwrap_or(1);
+        // Collect each group's guests, adults ahead of children
+        let mut groups: IndexMap<Group, Vec<&Guest>> = IndexMap::new();
+        for guest in guests {
+            groups.entry(guest.group()).or_default().push(guest);
+        }
+        for guests in groups.values_mut() {
+            guests.sort_by_key(|guest| guest.child());
+        }
+        let mut parties = Vec::new();
+        // Seat groups that must sit together as one party (if they fit)
+        for set in &constraints.together {
+            let size: usize = set
+                .iter()
+                .filter_map(|group| groups.get(group))
+                .map(Vec::len)
+                .sum();
+            if size > largest {
+                continue;
+            }
+            let guests = set
+                .iter()
+                .filter_map(|group| groups.shift_remove(group))
+                .flatten()
+                .collect_vec();
+            if !guests.is_empty() {
+                parties.push(Party { guests });
+            }
+        }
+        // Seat the remaining groups, split if too large for any table
+        for guests in groups.into_values() {
+            for chunk in guests.chunks(largest.max(1)) {
+                parties.push(Party {
+                    guests: chunk.to_vec(),
+                });
+            }
+        }
+        // Seat the largest parties first
+        parties.sort_by_key(|party| std::cmp::Reverse(party.guests.len()));
+        // Look up the side of each group
+        let sides = constraints
+            .sides
+            .iter()
+            .flat_map(|(side, groups)| groups.iter().map(move |&group| (group, side.as_str())))
+            .collect();
+
+        Self {
+            tables,
+            constraints,
+            parties,
+            sides,
+        }
+    }
+
+    fn solve(&self) -> Vec<Option<usize>> {
+        let mut seats = vec![None; self.parties.len()];
+        // Greedily seat each party at its best table
+        for idx in 0..self.parties.len() {
+            let mut best = None;
+            for table in 0..self.tables.len() {
+                seats[idx] = Some(table);
+                let score = self.score(&seats);
+                if best.is_none_or(|(_, best)| score < best) {
+                    best = Some((table, score));
+                }
+            }
+            seats[idx] = best.map(|(table, _)| table);
+        }
+        // Improve by moving or swapping parties until nothing helps
+        let mut best = self.score(&seats);
+        while best > 0 {
+            let prev = best;
+            for idx in 0..self.parties.len() {
+                for table in 0..self.tables.len() {
+                    if seats[idx] == Some(table) {
+                        continue;
+                    }
+                    let old = seats[idx].replace(table);
+                    let score = self.score(&seats);
+                    if score < best {
+                        best = score;
+                    } else {
+                        seats[idx] = old;
+                    }
+                }
+                for other in idx + 1..self.parties.len() {
+                    if seats[idx] == seats[other] {
+                        continue;
+                    }
+                    seats.swap(idx, other);
+                    let score = self.score(&seats);
+                    if score < best {
+                        best = score;
+                    } else {
+                        seats.swap(idx, other);
+                    }
+                }
+            }
+            if best == prev {
+                break;
+            }
+        }
+
+        seats
+    }
+
+    fn score(&self, seats: &[Option<usize>]) -> usize {
+        self.violations(seats).iter().map(Violation::penalty).sum()
+    }
+
+    fn violations(&self, seats: &[Option<usize>]) -> Vec<Violation<'a>> {
+        let mut violations = Vec::new();
+        let mut seated = vec![0; self.tables.len()];
+        let mut groups: IndexMap<Group, BTreeSet<usize>> = IndexMap::new();
+        let mut adults: IndexMap<Group, BTreeSet<usize>> = IndexMap::new();
+        for (party, &table) in self.parties.iter().zip(seats) {
+            let Some(table) = table else {
+                continue;
+            };
+            seated[table] += party.guests.len();
+            for guest in &party.guests {
+                groups.entry(guest.group()).or_default().insert(table);
+                if !guest.child() {
+                    adults.entry(guest.group()).or_default().insert(table);
+                }
+            }
+        }
+        // Check for tables over capacity
+        for (table, seated) in self.tables.iter().zip(seated) {
+            if seated > table.capacity {
+                violations.push(Violation::Crowded(&table.name, seated, table.capacity));
+            }
+        }
+        // Check for groups split across tables
+        for (group, tables) in &groups {
+            if tables.len() > 1 {
+                violations.push(Violation::Split(*group, tables.len()));
+            }
+        }
+        // Check for pairs of groups to keep together or apart
+        for (a, b) in pairs(&self.constraints.together) {
+            let (Some(x), Some(y)) = (groups.get(&a), groups.get(&b)) else {
+                continue;
+            };
+            if x.is_disjoint(y) {
+                violations.push(Violation::Together(a, b));
+            }
+        }
+        for (a, b) in pairs(&self.constraints.apart) {
+            let (Some(x), Some(y)) = (groups.get(&a), groups.get(&b)) else {
+                continue;
+            };
+            if let Some(&table) = x.intersection(y).next() {
+                violations.push(Violation::Apart(a, b, &self.tables[table].name));
+            }
+        }
+        // Check each guest's seat against their group and side
+        for (party, &table) in self.parties.iter().zip(seats) {
+            let Some(idx) = table else {
+                continue;
+            };
+            let table = &self.tables[idx];
+            for guest in &party.guests {
+                let strayed = adults
+                    .get(&guest.group())
+                    .is_some_and(|adults| !adults.contains(&idx));
+                if guest.child() && strayed {
+                    violations.push(Violation::Strayed(guest.user(), &table.name));
+                }
+                let side = self.sides.get(&guest.group()).copied();
+                if let (Some(side), Some(other)) = (side, table.side.as_deref()) {
+                    if side != other {
+                        violations.push(Violation::Side(guest.user(), side, &table.name));
+                    }
+                }
+            }
+        }
+
+        violations
+    }
+}
+
+fn pairs(sets: &[Vec<Group>]) -> impl Iterator<Item = (Group, Group)> + '_ {
+    sets.iter()
+        .flat_map(|set| set.iter().copied().tuple_combinations())
+}
+
+pub fn plan<'a>(db: &'a Database, constraints: &'a Constraints, pending: bool) -> Plan<'a> {
+    // Collect the guests to seat, in order of their group
+    let guests = db
+        .groups()
+        .flat_map(|(_, idents)| idents)
+        .filter_map(|ident| db.guest(ident))
+        .filter(|guest| {
+            // Seat guests attending the seated event, or yet to answer for it
+            let event = db.tables.event();
+            let answered = guest
+                .reply()
+                .answer(event)
+                .is_some_and(|answer| answer.attend.is_some());
+            guest.invited(event) && (guest.reply().attends(event) || (pending && !answered))
+        })
+        .collect_vec();
+    // Solve for the best seating
+    let solver = Solver::new(guests, db.tables.tables(), constraints);
+    let tables = solver.solve();
+    let violations = solver.violations(&tables);
+    let score = violations.iter().map(Violation::penalty).sum();
+    // Assign each guest to their party's table
+    let seats = solver
+        .parties
+        .iter()
+        .zip(tables)
+        .flat_map(|(party, table)| {
+            let table = table.map(|table| solver.tables[table].name.clone());
+            party.guests.iter().map(move |guest| Seat {
+                id: guest.user().ident,
+                group: guest.group(),
+                first: guest.user().first().to_string(),
+                last: guest.user().last().to_string(),
+                table: table.clone(),
+            })
+        })
+        .sorted_by_key(|seat| seat.group)
+        .collect();
+
+    Plan {
+        seats,
+        score,
+        violations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(group: Group, adults: usize, children: usize) -> Vec<Guest> {
+        (0..adults + children)
+            .map(|idx| {
+                let user = User::new(format!("Guest {idx}"), format!("Group {group}"));
+                Guest::new(group, user, idx >= adults)
+            })
+            .collect()
+    }
+
+    fn table(name: &str, capacity: usize, side: Option<&str>) -> Table {
+        Table {
+            name: name.to_string(),
+            capacity,
+            side: side.map(ToString::to_string),
+        }
+    }
+
+    fn solve(guests: &[Guest], tables: &[Table], constraints: &Constraints) -> Vec<usize> {
+        let solver = Solver::new(guests.iter().collect(), tables, constraints);
+        let seats = solver.solve();
+        assert_eq!(solver.score(&seats), 0);
+        // Report the table of each group's first guest
+        let mut groups = BTreeMap::new();
+        for (party, table) in solver.parties.iter().zip(seats) {
+            for guest in &party.guests {
+                groups.entry(guest.group()).or_insert(table.unwrap());
+            }
+        }
+        groups.into_values().collect()
+    }
+
+    #[test]
+    fn capacity() {
+        let guests = [group(1, 2, 0), group(2, 2, 0)].concat();
+        let tables = [table("A", 2, None), table("B", 2, None)];
+        let seats = solve(&guests, &tables, &Constraints::default());
+        assert_ne!(seats[0], seats[1]);
+    }
+
+    #[test]
+    fn together() {
+        let guests = [group(1, 2, 0), group(2, 2, 0), group(3, 2, 0)].concat();
+        let tables = [table("A", 4, None), table("B", 4, None)];
+        let constraints = Constraints {
+            together: vec![vec![1, 3]],
+            ..Default::default()
+        };
+        let seats = solve(&guests, &tables, &constraints);
+        assert_eq!(seats[0], seats[2]);
+    }
+
+    #[test]
+    fn apart() {
+        let guests = [group(1, 2, 0), group(2, 2, 0)].concat();
+        let tables = [table("A", 6, None), table("B", 6, None)];
+        let constraints = Constraints {
+            apart: vec![vec![1, 2]],
+            ..Default::default()
+        };
+        let seats = solve(&guests, &tables, &constraints);
+        assert_ne!(seats[0], seats[1]);
+    }
+
+    #[test]
+    fn strayed() {
+        let guests = group(1, 1, 1);
+        let tables = [table("A", 1, None), table("B", 1, None)];
+        let constraints = Constraints::default();
+        let solver = Solver::new(guests.iter().collect(), &tables, &constraints);
+        // Seat the child away from the adult
+        assert!(!solver.parties[0].guests[0].child());
+        let violations = solver.violations(&[Some(0), Some(1)]);
+        assert!(matches!(
+            violations.as_slice(),
+            [Violation::Split(1, 2), Violation::Strayed(_, "B")]
+        ));
+    }
+
+    #[test]
+    fn chunked() {
+        let guests = group(1, 3, 0);
+        let tables = [table("A", 2, None), table("B", 2, None)];
+        let constraints = Constraints::default();
+        let solver = Solver::new(guests.iter().collect(), &tables, &constraints);
+        // Split the group to fit the largest table
+        let sizes = solver.parties.iter().map(|party| party.guests.len());
+        assert_eq!(sizes.collect_vec(), [2, 1]);
+        let seats = solver.solve();
+        assert_eq!(solver.score(&seats), SPLIT);
+    }
+
+    #[test]
+    fn score() {
+        let guests = [group(1, 2, 0), group(2, 1, 1), group(3, 1, 0)].concat();
+        let tables = [table("A", 2, Some("bride")), table("B", 2, None)];
+        let constraints = Constraints {
+            together: vec![vec![1, 3]],
+            apart: vec![vec![1, 2]],
+            sides: BTreeMap::from([("groom".to_string(), vec![1])]),
+        };
+        let solver = Solver::new(guests.iter().collect(), &tables, &constraints);
+        let seats = [Some(0), Some(0), Some(1)];
+        // Crowded by 2, groups 1 and 3 apart, 1 and 2 together, group 1 on the wrong side
+        let violations = solver.violations(&seats);
+        assert_eq!(violations.len(), 5);
+        let total: usize = violations.iter().map(Violation::penalty).sum();
+        assert_eq!(total, 2 * CROWDED + TOGETHER + APART + 2 * SIDE);
+        assert_eq!(solver.score(&seats), total);
+    }
+}
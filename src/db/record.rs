@@ -1,38 +1,108 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
-use super::guest::{Attend, Guest, Meal, Message, Reply};
-use super::Group;
+use super::event;
+use super::guest::{Answer, Attend, Diets, Events, Guest, Meal, Message, Reply};
+use super::{Group, Ident};
 use crate::user::User;
 
+/// Columns written for each record, in order.
+///
+/// Event answers are written separately, as they depend on the schedule.
+pub(super) const COLUMNS: [&str; 14] = [
+    "id", "group", "first", "last", "child", "code", "email", "events", "plus_one", "host",
+    "table", "diet", "note", "msg",
+];
+
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub(super) struct Record {
+    #[serde(default)]
+    id: Option<Ident>,
     group: Group,
     first: String,
     last: String,
     #[serde(default)]
     child: bool,
+    #[serde(default)]
+    code: Option<String>,
+    #[serde(default)]
+    email: Option<String>,
+    #[serde(default)]
+    events: Events,
+    #[serde(default)]
+    plus_one: bool,
+    #[serde(default)]
+    host: Option<Ident>,
+    #[serde(default)]
+    table: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    answers: BTreeMap<String, Answer>,
+    #[serde(default, skip_serializing)]
     attend: Option<Attend>,
+    #[serde(default, skip_serializing)]
     meal: Option<Meal>,
+    #[serde(default)]
+    diet: Diets,
+    #[serde(default)]
+    note: Option<Message>,
     msg: Option<Message>,
 }
 
+impl Record {
+    pub(super) fn ident(&self) -> Option<Ident> {
+        self.id
+    }
+}
+
 impl From<Record> for Guest {
     fn from(
         Record {
+            id,
             group,
             first,
             last,
             child,
+            code,
+            email,
+            events,
+            plus_one,
+            host,
+            table,
+            mut answers,
             attend,
             meal,
+            diet,
+            note,
             msg,
         }: Record,
     ) -> Self {
+        let mut user = User::new(first, last);
+        if let Some(ident) = id {
+            user.ident = ident;
+        }
+        // Answer the default event with replies from before events existed
+        if attend.is_some() || meal.is_some() {
+            answers
+                .entry(event::DEFAULT.to_string())
+                .or_insert(Answer { attend, meal });
+        }
         Self {
             group,
-            user: User::new(first, last),
+            user,
             child,
-            reply: Reply { attend, meal, msg },
+            code,
+            email,
+            events,
+            plus_one,
+            host,
+            table,
+            reply: Reply {
+                events: answers,
+                diet,
+                note,
+                msg,
+            },
         }
     }
 }
@@ -43,18 +113,41 @@ impl From<Guest> for Record {
             group,
             user,
             child,
+            code,
+            email,
+            events,
+            plus_one,
+            host,
+            table,
             reply,
         }: Guest,
     ) -> Self {
-        let User { first, last, .. } = user;
-        let Reply { attend, meal, msg } = reply;
+        let User {
+            ident, first, last, ..
+        } = user;
+        let Reply {
+            events: answers,
+            diet,
+            note,
+            msg,
+        } = reply;
         Self {
+            id: Some(ident),
             group,
             first,
             last,
             child,
-            attend,
-            meal,
+            code,
+            email,
+            events,
+            plus_one,
+            host,
+            table,
+            answers,
+            attend: None,
+            meal: None,
+            diet,
+            note,
             msg,
         }
     }
@@ -0,0 +1,137 @@
+use std::collections::BTreeSet;
+use std::fmt::Display;
+
+use indexmap::IndexMap;
+use serde::Deserialize;
+
+use super::guest::Guest;
+use super::{Database, Group};
+
+#[derive(Clone, Debug, Default)]
+pub struct Tables {
+    event: String,
+    tables: Vec<Table>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Table {
+    pub name: String,
+    pub capacity: usize,
+    #[serde(default)]
+    pub side: Option<String>,
+}
+
+impl Tables {
+    pub fn new(event: String, tables: Vec<Table>) -> Self {
+        Self { event, tables }
+    }
+
+    pub fn event(&self) -> &str {
+        &self.event
+    }
+
+    pub fn tables(&self) -> &[Table] {
+        &self.tables
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Table> {
+        self.tables.iter().find(|table| table.name == name)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Chart {
+    pub tables: Vec<Seats>,
+    pub warnings: Vec<Warning>,
+}
+
+#[derive(Debug)]
+pub struct Seats {
+    pub table: Table,
+    pub guests: Vec<Guest>,
+    pub attending: usize,
+}
+
+#[derive(Debug)]
+pub enum Warning {
+    Full(String, usize, usize),
+    Split(Group, Vec<String>),
+    Unknown(String, String),
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::Full(table, attending, capacity) => {
+                write!(f, "{table} is over capacity: {attending} / {capacity}")
+            }
+            Warning::Split(group, tables) => {
+                write!(f, "group {group} is split across: {}", tables.join(", "))
+            }
+            Warning::Unknown(name, table) => {
+                write!(f, "{name} is seated at an unknown table: {table}")
+            }
+        }
+    }
+}
+
+impl From<&Database> for Chart {
+    fn from(db: &Database) -> Self {
+        let mut chart = Chart::default();
+        let mut seats: IndexMap<&str, Seats> = db
+            .tables
+            .tables()
+            .iter()
+            .map(|table| {
+                let seats = Seats {
+                    table: table.clone(),
+                    guests: Vec::new(),
+                    attending: 0,
+                };
+                (table.name.as_str(), seats)
+            })
+            .collect();
+        let mut groups: IndexMap<Group, BTreeSet<&str>> = IndexMap::new();
+
+        for guest in db.guests.values() {
+            let Some(table) = guest.table() else {
+                continue;
+            };
+            // Track the tables of each group
+            groups.entry(guest.group()).or_default().insert(table);
+            // Seat the guest at their table
+            let Some(seats) = seats.get_mut(table) else {
+                let name = guest.user().name();
+                chart
+                    .warnings
+                    .push(Warning::Unknown(name, table.to_string()));
+                continue;
+            };
+            // Only count guests who are attending the seated event
+            if guest.reply().attends(db.tables.event()) {
+                seats.attending += 1;
+            }
+            seats.guests.push(guest.clone());
+        }
+        // Check for tables over capacity
+        for seats in seats.values() {
+            if seats.attending > seats.table.capacity {
+                chart.warnings.push(Warning::Full(
+                    seats.table.name.clone(),
+                    seats.attending,
+                    seats.table.capacity,
+                ));
+            }
+        }
+        // Check for groups split across tables
+        for (group, tables) in groups {
+            if tables.len() > 1 {
+                let tables = tables.into_iter().map(ToString::to_string).collect();
+                chart.warnings.push(Warning::Split(group, tables));
+            }
+        }
+        chart.tables = seats.into_values().collect();
+
+        chart
+    }
+}
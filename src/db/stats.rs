@@ -0,0 +1,161 @@
+use indexmap::IndexMap;
+
+use super::event::Event;
+use super::guest::{Attend, Diet, Meal, Message};
+use super::{Database, Group};
+
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub guests: usize,
+    pub children: usize,
+    pub events: Vec<Tally>,
+    pub diets: IndexMap<Diet, usize>,
+    pub notes: Vec<(String, Message)>,
+    pub groups: Vec<Progress>,
+    pub messages: Vec<(String, Message)>,
+}
+
+#[derive(Debug)]
+pub struct Tally {
+    pub event: Event,
+    pub invited: usize,
+    pub yes: usize,
+    pub no: usize,
+    pub pending: usize,
+    pub children: usize,
+    pub children_yes: usize,
+    pub meals: IndexMap<Meal, Serving>,
+}
+
+#[derive(Debug)]
+pub struct Serving {
+    pub label: String,
+    pub count: usize,
+}
+
+impl Serving {
+    fn new(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            count: 0,
+        }
+    }
+}
+
+impl Tally {
+    fn new(db: &Database, event: &Event) -> Self {
+        let meals = if event.meals {
+            db.menu
+                .choices()
+                .iter()
+                .map(|choice| (choice.id.clone(), Serving::new(&choice.label)))
+                .collect()
+        } else {
+            IndexMap::new()
+        };
+        Self {
+            event: event.clone(),
+            invited: 0,
+            yes: 0,
+            no: 0,
+            pending: 0,
+            children: 0,
+            children_yes: 0,
+            meals,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Progress {
+    pub group: Group,
+    pub names: Vec<String>,
+    pub responded: usize,
+    pub total: usize,
+}
+
+impl Progress {
+    pub fn complete(&self) -> bool {
+        self.responded == self.total
+    }
+}
+
+impl From<&Database> for Stats {
+    fn from(db: &Database) -> Self {
+        let mut stats = Stats {
+            events: db
+                .schedule
+                .events()
+                .iter()
+                .map(|event| Tally::new(db, event))
+                .collect(),
+            diets: Diet::ALL.into_iter().map(|diet| (diet, 0)).collect(),
+            ..Default::default()
+        };
+        // Tally each guest's reply
+        for guest in db.guests.values() {
+            let reply = guest.reply();
+            stats.guests += 1;
+            if guest.child() {
+                stats.children += 1;
+            }
+            // Tally each event the guest is invited to
+            for tally in &mut stats.events {
+                if !guest.invited(&tally.event.id) {
+                    continue;
+                }
+                let answer = reply.answer(&tally.event.id).cloned().unwrap_or_default();
+                tally.invited += 1;
+                match answer.attend {
+                    Some(Attend::Yes) => tally.yes += 1,
+                    Some(Attend::No) => tally.no += 1,
+                    None => tally.pending += 1,
+                }
+                if guest.child() {
+                    tally.children += 1;
+                    if matches!(answer.attend, Some(Attend::Yes)) {
+                        tally.children_yes += 1;
+                    }
+                }
+                if let Some(meal) = &answer.meal {
+                    let serving = tally
+                        .meals
+                        .entry(meal.clone())
+                        .or_insert_with(|| Serving::new(db.menu.label(meal)));
+                    serving.count += 1;
+                }
+            }
+            for diet in reply.diet.iter() {
+                *stats.diets.entry(*diet).or_default() += 1;
+            }
+            if let Some(note) = reply.note.as_ref().filter(|note| !note.trim().is_empty()) {
+                stats.notes.push((guest.user().name(), note.clone()));
+            }
+            if let Some(msg) = reply.msg.as_ref().filter(|msg| !msg.trim().is_empty()) {
+                stats.messages.push((guest.user().name(), msg.clone()));
+            }
+        }
+        // Summarize each group's progress
+        stats.groups = db
+            .groups
+            .iter()
+            .map(|(group, idents)| {
+                let guests = idents
+                    .iter()
+                    .filter_map(|ident| db.guests.get(ident))
+                    .collect::<Vec<_>>();
+                Progress {
+                    group: *group,
+                    names: guests.iter().map(|guest| guest.user().name()).collect(),
+                    responded: guests
+                        .iter()
+                        .filter(|guest| db.schedule.answered(guest))
+                        .count(),
+                    total: guests.len(),
+                }
+            })
+            .collect();
+
+        stats
+    }
+}
@@ -0,0 +1,346 @@
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use log::{debug, info, trace, warn};
+use serde::{Deserialize, Serialize};
+
+use super::{Pending, Store};
+use crate::db::guest::Guest;
+use crate::db::history::Revision;
+use crate::db::record::{Record, COLUMNS};
+use crate::db::{Error, Ident};
+
+/// Line of the journal.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum Entry {
+    Remove { removed: Ident },
+    Update(Box<Record>),
+}
+
+/// Guests read from the input file.
+#[derive(Debug)]
+pub struct Lines {
+    /// Each guest, along with its line number.
+    pub entries: Vec<(u64, Guest)>,
+    /// Line numbers of guests without an identifier.
+    pub missing: Vec<u64>,
+}
+
+#[derive(Debug)]
+pub struct Csv {
+    path: PathBuf,
+    journal: PathBuf,
+    sealed: PathBuf,
+    history: PathBuf,
+    events: Vec<String>,
+}
+
+impl Csv {
+    pub fn new(path: PathBuf, events: Vec<String>) -> Self {
+        let journal = sibling(&path, "journal");
+        let sealed = sibling(&journal, "sealed");
+        let history = sibling(&path, "history");
+        Self {
+            path,
+            journal,
+            sealed,
+            history,
+            events,
+        }
+    }
+
+    pub fn lines(&self) -> Result<Lines, Error> {
+        // Open the input file
+        let mut reader = csv::Reader::from_path(&self.path)?;
+        let headers = reader.headers()?.clone();
+        // Read the guests along with their line numbers
+        debug!("reading: `{}`", self.path.display());
+        let mut missing = Vec::new();
+        let mut entries: Vec<(u64, Guest)> = Vec::new();
+        for row in reader.records() {
+            let row = row?;
+            let line = row.position().map_or(0, csv::Position::line);
+            let record: Record = row.deserialize(Some(&headers))?;
+            if record.ident().is_none() {
+                missing.push(line);
+            }
+            // Read each event's answers from their own columns
+            let mut guest = Guest::from(record);
+            for (key, value) in headers.iter().zip(row.iter()) {
+                guest
+                    .reply
+                    .set(key, value)
+                    .map_err(|err| Error::Reply(format!("line {line}: {err}")))?;
+            }
+            entries.push((line, guest));
+        }
+
+        Ok(Lines { entries, missing })
+    }
+
+    pub fn assign(&self) -> Result<usize, Error> {
+        // Read the input file as is, keeping every column
+        let mut reader = csv::Reader::from_path(&self.path)?;
+        let mut headers: Vec<String> = reader.headers()?.iter().map(String::from).collect();
+        let mut rows = reader
+            .records()
+            .map(|row| Ok(row?.iter().map(String::from).collect()))
+            .collect::<Result<Vec<Vec<String>>, Error>>()?;
+        // Add an id column (if missing)
+        let col = match headers.iter().position(|header| header == "id") {
+            Some(col) => col,
+            None => {
+                headers.insert(0, "id".to_string());
+                for row in &mut rows {
+                    row.insert(0, String::new());
+                }
+                0
+            }
+        };
+        // Generate an identifier for each guest missing one
+        let mut count = 0;
+        for row in &mut rows {
+            if row.get(col).is_some_and(|id| id.trim().is_empty()) {
+                row[col] = Ident::default().to_string();
+                count += 1;
+            }
+        }
+        if count == 0 {
+            return Ok(0);
+        }
+        // Write to a temporary file alongside the input
+        let tmp = sibling(&self.path, "tmp");
+        let mut writer = csv::WriterBuilder::new().flexible(true).from_path(&tmp)?;
+        debug!("writing: `{}`", self.path.display());
+        writer.write_record(&headers)?;
+        for row in &rows {
+            writer.write_record(row)?;
+        }
+        let file = writer.into_inner().map_err(|err| err.into_error())?;
+        file.sync_all()?;
+        // Atomically replace the input
+        fs::rename(&tmp, &self.path)?;
+        sync(&self.path)?;
+        info!("assigned {count} identifiers");
+
+        Ok(count)
+    }
+
+    fn save(&self, guests: &[Guest]) -> Result<(), Error> {
+        // Open a temporary file alongside the output
+        let tmp = sibling(&self.path, "tmp");
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_path(&tmp)?;
+        debug!("writing: `{}`", self.path.display());
+        // Keep answers to events no longer scheduled
+        let mut events = self.events.clone();
+        for event in guests.iter().flat_map(|guest| guest.reply.events.keys()) {
+            if !events.contains(event) {
+                events.push(event.clone());
+            }
+        }
+        // Write the headers, with columns for each event
+        let mut headers: Vec<_> = COLUMNS.iter().map(ToString::to_string).collect();
+        for event in &events {
+            headers.push(format!("{event}.attend"));
+            headers.push(format!("{event}.meal"));
+        }
+        writer.write_record(&headers)?;
+        // Write the guests
+        for guest in guests {
+            // Split the guest's answers from the rest of the record
+            let mut rest = guest.clone();
+            let answers = std::mem::take(&mut rest.reply.events);
+            let record = Record::from(rest);
+            let cells: Vec<_> = events
+                .iter()
+                .flat_map(|event| {
+                    let answer = answers.get(event).cloned().unwrap_or_default();
+                    [
+                        answer.attend.map(|attend| attend.to_string()),
+                        answer.meal.map(|meal| meal.to_string()),
+                    ]
+                    .map(Option::unwrap_or_default)
+                })
+                .collect();
+            // Serialize and write it
+            writer.serialize((record, cells)).map_err(Error::Csv)?;
+            trace!("wrote: `{}`, reply: {}", guest.user(), guest.reply());
+        }
+        // Ensure the file is fully written to disk
+        let file = writer.into_inner().map_err(|err| err.into_error())?;
+        file.sync_all()?;
+        // Atomically replace the output
+        fs::rename(&tmp, &self.path)?;
+        sync(&self.path)?;
+
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), Error> {
+        // Remove the sealed journal (if any)
+        match fs::remove_file(&self.sealed) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Store for Csv {
+    fn read(&self) -> Result<Vec<Guest>, Error> {
+        // Read the guests, ignoring their line numbers
+        Ok(self
+            .lines()?
+            .entries
+            .into_iter()
+            .map(|(_, guest)| guest)
+            .collect())
+    }
+
+    fn write(&self, guests: &[Guest]) -> Result<(), Error> {
+        // Save the guests
+        self.save(guests)?;
+        // Clear the sealed journal, as its changes are now saved
+        self.clear()
+    }
+
+    fn journal(&self, pending: &[Pending], revisions: &[Revision]) -> Result<(), Error> {
+        // Record the history ahead of the changes it describes
+        self.record(revisions)?;
+        if pending.is_empty() {
+            return Ok(());
+        }
+        // Append the changes to the journal
+        let mut journal = File::options()
+            .append(true)
+            .create(true)
+            .open(&self.journal)?;
+        for change in pending {
+            let entry = match change {
+                Pending::Update(guest) => {
+                    trace!("journal: `{}`, reply: {}", guest.user(), guest.reply());
+                    Entry::Update(Box::new(Record::from(*guest.clone())))
+                }
+                Pending::Remove(ident) => {
+                    trace!("journal: removed: `{ident}`");
+                    Entry::Remove { removed: *ident }
+                }
+            };
+            let mut line = serde_json::to_string(&entry)?;
+            line.push('\n');
+            journal.write_all(line.as_bytes())?;
+        }
+        // Ensure the changes are on disk before acknowledging them
+        journal.sync_data()?;
+
+        Ok(())
+    }
+
+    fn seal(&self) -> Result<(), Error> {
+        // Read the journal (if any)
+        let data = match fs::read(&self.journal) {
+            Ok(data) => data,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        // Move its changes to the sealed journal
+        let mut sealed = File::options()
+            .append(true)
+            .create(true)
+            .open(&self.sealed)?;
+        sealed.write_all(&data)?;
+        sealed.sync_data()?;
+        fs::remove_file(&self.journal)?;
+        sync(&self.journal)?;
+
+        Ok(())
+    }
+
+    fn pending(&self) -> Result<Vec<Pending>, Error> {
+        // Read the sealed journal, then any changes since
+        let mut pending = Vec::new();
+        for path in [&self.sealed, &self.journal] {
+            // Open the journal (if any)
+            let file = match File::open(path) {
+                Ok(file) => file,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+            debug!("reading: `{}`", path.display());
+            // Read each journaled change
+            for (idx, line) in BufReader::new(file).lines().enumerate() {
+                match serde_json::from_str(&line?) {
+                    Ok(Entry::Update(record)) => {
+                        pending.push(Pending::Update(Box::new((*record).into())));
+                    }
+                    Ok(Entry::Remove { removed }) => pending.push(Pending::Remove(removed)),
+                    // Skip entries torn by a crash during append
+                    Err(err) => warn!("journal: skipping line {}: {err}", idx + 1),
+                }
+            }
+        }
+
+        Ok(pending)
+    }
+
+    fn history(&self) -> Result<Vec<Revision>, Error> {
+        // Open the history (if any)
+        let file = match File::open(&self.history) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::default()),
+            Err(err) => return Err(err.into()),
+        };
+        debug!("reading: `{}`", self.history.display());
+        // Read each revision
+        let mut revisions = Vec::new();
+        for (idx, line) in BufReader::new(file).lines().enumerate() {
+            match serde_json::from_str(&line?) {
+                Ok(revision) => revisions.push(revision),
+                // Skip entries torn by a crash during append
+                Err(err) => warn!("history: skipping line {}: {err}", idx + 1),
+            }
+        }
+
+        Ok(revisions)
+    }
+
+    fn record(&self, revisions: &[Revision]) -> Result<(), Error> {
+        if revisions.is_empty() {
+            return Ok(());
+        }
+        // Append the revisions to the history
+        let mut history = File::options()
+            .append(true)
+            .create(true)
+            .open(&self.history)?;
+        for revision in revisions {
+            let mut line = serde_json::to_string(revision)?;
+            line.push('\n');
+            history.write_all(line.as_bytes())?;
+        }
+        history.sync_data()?;
+        trace!("history: {} revisions", revisions.len());
+
+        Ok(())
+    }
+}
+
+fn sibling(path: &Path, ext: &str) -> PathBuf {
+    let mut name = OsString::from(path);
+    name.push(".");
+    name.push(ext);
+    name.into()
+}
+
+fn sync(path: &Path) -> io::Result<()> {
+    // Flush the directory entry after a rename
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()
+}
@@ -0,0 +1,62 @@
+use std::fmt::Debug;
+
+use super::guest::Guest;
+use super::history::Revision;
+use super::{Error, Ident};
+
+mod csv;
+mod sqlite;
+
+pub use self::csv::{Csv, Lines};
+pub use self::sqlite::Sqlite;
+
+/// Change to a guest, journaled until it is written in full.
+#[derive(Clone, Debug)]
+pub enum Pending {
+    /// Guest was inserted or updated.
+    Update(Box<Guest>),
+    /// Guest was removed.
+    Remove(Ident),
+}
+
+/// Persistent storage backend for the guestlist.
+pub trait Store: Debug + Send + Sync {
+    /// Reads all guests from the store.
+    fn read(&self) -> Result<Vec<Guest>, Error>;
+
+    /// Writes all guests to the store, replacing its contents.
+    fn write(&self, guests: &[Guest]) -> Result<(), Error>;
+
+    /// Writes the updated guests to the store.
+    ///
+    /// Stores which cannot update individual guests rewrite all of them.
+    fn update(&self, guests: &[Guest], idents: &[Ident]) -> Result<(), Error> {
+        let _ = idents;
+        self.write(guests)
+    }
+
+    /// Appends changes to the journal, before they are written in full,
+    /// along with the revisions describing them.
+    fn journal(&self, pending: &[Pending], revisions: &[Revision]) -> Result<(), Error> {
+        let _ = pending;
+        self.record(revisions)
+    }
+
+    /// Seals the journal's changes, which the next write will clear.
+    ///
+    /// Changes journaled after sealing are kept until the following write.
+    fn seal(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Reads any changes which were not yet written in full.
+    fn pending(&self) -> Result<Vec<Pending>, Error> {
+        Ok(Vec::default())
+    }
+
+    /// Reads the history of revisions to replies.
+    fn history(&self) -> Result<Vec<Revision>, Error>;
+
+    /// Appends revisions to the history.
+    fn record(&self, revisions: &[Revision]) -> Result<(), Error>;
+}
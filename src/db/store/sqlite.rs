@@ -0,0 +1,179 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use log::{debug, trace};
+use rusqlite::{params, Connection};
+use uuid::Uuid;
+
+use super::{Pending, Store};
+use crate::db::guest::Guest;
+use crate::db::history::Revision;
+use crate::db::record::Record;
+use crate::db::{Error, Ident};
+
+#[derive(Debug)]
+pub struct Sqlite {
+    conn: Mutex<Connection>,
+}
+
+impl Sqlite {
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        // Open the database file
+        let conn = Connection::open(path)?;
+        debug!("opened: `{}`", path.display());
+        // Prepare the schema
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS guests (
+                id   TEXT PRIMARY KEY,
+                pos  INTEGER NOT NULL,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS history (
+                pos  INTEGER PRIMARY KEY AUTOINCREMENT,
+                data TEXT NOT NULL
+            );",
+        )?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+impl Store for Sqlite {
+    fn read(&self) -> Result<Vec<Guest>, Error> {
+        let conn = self.conn.lock().unwrap();
+        // Query all guests in order
+        let mut stmt = conn.prepare("SELECT id, data FROM guests ORDER BY pos")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        // Read the guests
+        rows.map(|row| {
+            let (id, data) = row?;
+            // Parse the stored record
+            let record: Record = serde_json::from_str(&data)?;
+            let mut guest = Guest::from(record);
+            // Restore the guest's identifier
+            guest.user.ident = Ident(Uuid::parse_str(&id)?);
+            trace!("read: `{}`, reply: {}", guest.user(), guest.reply());
+            Ok(guest)
+        })
+        .collect()
+    }
+
+    fn write(&self, guests: &[Guest]) -> Result<(), Error> {
+        let mut conn = self.conn.lock().unwrap();
+        // Replace all guests in a single transaction
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM guests", [])?;
+        for (pos, guest) in guests.iter().enumerate() {
+            insert(&tx, pos, guest)?;
+        }
+        tx.commit()?;
+        debug!("wrote: {} guests", guests.len());
+
+        Ok(())
+    }
+
+    fn update(&self, guests: &[Guest], idents: &[Ident]) -> Result<(), Error> {
+        let mut conn = self.conn.lock().unwrap();
+        // Write only the updated guests in a single transaction
+        let tx = conn.transaction()?;
+        for ident in idents {
+            match guests.iter().position(|guest| guest.user().ident == *ident) {
+                Some(pos) => insert(&tx, pos, &guests[pos])?,
+                // Delete guests which were removed
+                None => remove(&tx, ident)?,
+            }
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    fn journal(&self, pending: &[Pending], revisions: &[Revision]) -> Result<(), Error> {
+        let mut conn = self.conn.lock().unwrap();
+        // Write the changes and their history straight away in a single transaction
+        let tx = conn.transaction()?;
+        for revision in revisions {
+            append(&tx, revision)?;
+        }
+        for change in pending {
+            match change {
+                Pending::Update(guest) => {
+                    // Keep the guest's position, or append them
+                    let pos: usize = tx.query_row(
+                        "SELECT COALESCE(
+                            (SELECT pos FROM guests WHERE id = ?1),
+                            (SELECT MAX(pos) + 1 FROM guests),
+                            0
+                        )",
+                        params![guest.user().ident.to_string()],
+                        |row| row.get(0),
+                    )?;
+                    insert(&tx, pos, guest)?;
+                }
+                Pending::Remove(ident) => remove(&tx, ident)?,
+            }
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    fn history(&self) -> Result<Vec<Revision>, Error> {
+        let conn = self.conn.lock().unwrap();
+        // Query all revisions in order
+        let mut stmt = conn.prepare("SELECT data FROM history ORDER BY pos")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        // Parse the stored revisions
+        rows.map(|row| Ok(serde_json::from_str(&row?)?)).collect()
+    }
+
+    fn record(&self, revisions: &[Revision]) -> Result<(), Error> {
+        let mut conn = self.conn.lock().unwrap();
+        // Append the revisions in a single transaction
+        let tx = conn.transaction()?;
+        for revision in revisions {
+            append(&tx, revision)?;
+        }
+        tx.commit()?;
+        trace!("history: {} revisions", revisions.len());
+
+        Ok(())
+    }
+}
+
+fn insert(conn: &Connection, pos: usize, guest: &Guest) -> Result<(), Error> {
+    // Serialize the guest as a record
+    let data = serde_json::to_string(&Record::from(guest.clone()))?;
+    // Insert or replace the guest's row
+    conn.execute(
+        "INSERT INTO guests (id, pos, data) VALUES (?1, ?2, ?3)
+         ON CONFLICT(id) DO UPDATE SET pos = excluded.pos, data = excluded.data",
+        params![guest.user().ident.to_string(), pos, data],
+    )?;
+    trace!("wrote: `{}`, reply: {}", guest.user(), guest.reply());
+
+    Ok(())
+}
+
+fn append(conn: &Connection, revision: &Revision) -> Result<(), Error> {
+    // Insert the revision after all others
+    let data = serde_json::to_string(revision)?;
+    conn.execute("INSERT INTO history (data) VALUES (?1)", params![data])?;
+
+    Ok(())
+}
+
+fn remove(conn: &Connection, ident: &Ident) -> Result<(), Error> {
+    // Delete the guest's row
+    conn.execute(
+        "DELETE FROM guests WHERE id = ?1",
+        params![ident.to_string()],
+    )?;
+    trace!("deleted: `{ident}`");
+
+    Ok(())
+}
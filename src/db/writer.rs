@@ -0,0 +1,126 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use log::{debug, error, info};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tokio::sync::RwLock;
+use tokio::task::{self, JoinHandle};
+use tokio::time;
+
+use super::journal::{self, Journal};
+use super::{Database, Error, Ident};
+use crate::metrics;
+
+#[derive(Debug, Default)]
+pub struct Status {
+    flushed: Option<SystemTime>,
+    error: Option<String>,
+}
+
+impl Status {
+    pub fn healthy(&self) -> bool {
+        self.error.is_none()
+    }
+
+    pub fn flushed(&self) -> Option<SystemTime> {
+        self.flushed
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
+
+pub async fn spawn(db: Arc<RwLock<Database>>, delay: Duration) -> Option<JoinHandle<()>> {
+    // Only spawn a writer when there is a store
+    let mut guard = db.write().await;
+    let store = guard.store.clone()?;
+    // Connect the database to the writer and journal
+    let (tx, rx) = mpsc::unbounded_channel();
+    let (journal, thread) = Journal::spawn(store);
+    guard.tx = Some(tx);
+    guard.journal = Some(journal.clone());
+    drop(guard);
+    // Spawn the writer task
+    Some(tokio::spawn(async move {
+        run(db, rx, journal, delay).await;
+        // Finish journaling before the writer is joined
+        if let Err(err) = thread.await {
+            error!("journal: {err}");
+        }
+    }))
+}
+
+async fn run(
+    db: Arc<RwLock<Database>>,
+    mut rx: UnboundedReceiver<Ident>,
+    journal: Journal,
+    delay: Duration,
+) {
+    // Keep track of updates that failed to flush
+    let mut failed = Vec::new();
+    while let Some(ident) = rx.recv().await {
+        // Collect any further updates during the delay
+        let mut idents: Vec<Ident> = std::mem::take(&mut failed);
+        if !idents.contains(&ident) {
+            idents.push(ident);
+        }
+        let timeout = time::sleep(delay);
+        tokio::pin!(timeout);
+        loop {
+            tokio::select! {
+                () = &mut timeout => break,
+                next = rx.recv() => match next {
+                    Some(ident) if !idents.contains(&ident) => idents.push(ident),
+                    Some(_) => (),
+                    // Flush immediately once closed
+                    None => break,
+                },
+            }
+        }
+        // Snapshot the database
+        let (store, guests, sealed) = {
+            let db = db.read().await;
+            let Some(store) = db.store.clone() else {
+                continue;
+            };
+            // Seal the journaled changes covered by the snapshot
+            let sealed = journal.seal();
+            (
+                store,
+                db.guests.values().cloned().collect::<Vec<_>>(),
+                sealed,
+            )
+        };
+        // Write the updates off the async runtime
+        debug!("flushing: {} updates", idents.len());
+        let sealed = journal::wait(sealed).await;
+        let batch = idents.clone();
+        let res = task::spawn_blocking(move || {
+            metrics::write(|| {
+                sealed?;
+                store.update(&guests, &batch)
+            })
+        })
+        .await
+        .unwrap_or_else(|err| Err(Error::Flush(err.to_string())));
+        // Record the outcome
+        let mut db = db.write().await;
+        let status = &mut db.status;
+        match res {
+            Ok(()) => {
+                if status.error.take().is_some() {
+                    info!("flush: recovered");
+                }
+                status.flushed = Some(SystemTime::now());
+            }
+            Err(err) => {
+                error!("flush: {err}");
+                status.error = Some(err.to_string());
+                // Retry these updates on the next flush
+                failed = idents;
+            }
+        }
+    }
+    debug!("writer: closed");
+}
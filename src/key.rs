@@ -0,0 +1,34 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+use log::{debug, info};
+use rand::Rng;
+
+pub type Key = [u8; 64];
+
+pub fn load(path: &Path) -> io::Result<Key> {
+    match fs::read(path) {
+        // Use the existing key
+        Ok(data) => {
+            debug!("key: reading: `{}`", path.display());
+            data.try_into().map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "key must be exactly 64 bytes")
+            })
+        }
+        // Generate a new key
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            info!("key: generating: `{}`", path.display());
+            let key: Key = rand::thread_rng().gen();
+            File::options()
+                .write(true)
+                .create_new(true)
+                .mode(0o600)
+                .open(path)?
+                .write_all(&key)?;
+            Ok(key)
+        }
+        Err(err) => Err(err),
+    }
+}
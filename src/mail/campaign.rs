@@ -0,0 +1,127 @@
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+
+use askama::Template;
+use chrono::Utc;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::db::deadline::Deadline;
+use crate::db::guest::Guest;
+use crate::db::Group;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Kind {
+    /// Invite every group.
+    Invite,
+    /// Remind groups that have not yet replied.
+    Remind,
+}
+
+impl Kind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Invite => "invite",
+            Kind::Remind => "remind",
+        }
+    }
+
+    pub fn subject(self) -> &'static str {
+        match self {
+            Kind::Invite => "You're invited to Hannah & Zakhary's wedding!",
+            Kind::Remind => "Reminder: please RSVP to Hannah & Zakhary's wedding",
+        }
+    }
+
+    pub fn wants(self, guests: &[&Guest]) -> bool {
+        match self {
+            Kind::Invite => true,
+            Kind::Remind => guests.iter().any(|guest| !guest.reply().responded()),
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "mail/invite.txt")]
+pub struct Invitation<'a> {
+    pub names: &'a str,
+    pub url: &'a str,
+    pub deadline: Option<Deadline>,
+}
+
+#[derive(Template)]
+#[template(path = "mail/remind.txt")]
+pub struct Reminder<'a> {
+    pub names: &'a str,
+    pub guests: &'a [&'a Guest],
+    pub url: &'a str,
+    pub deadline: Option<Deadline>,
+}
+
+pub fn names(guests: &[&Guest]) -> String {
+    // Join first names, e.g. "Ann, Bob & Cat"
+    let names: Vec<_> = guests.iter().map(|guest| guest.user().first()).collect();
+    match names.split_last() {
+        Some((last, [])) => (*last).to_string(),
+        Some((last, rest)) => format!("{} & {last}", rest.join(", ")),
+        None => String::new(),
+    }
+}
+
+/// Record of every message sent, so nobody is emailed twice.
+pub struct Log {
+    sent: HashSet<(String, String)>,
+    writer: csv::Writer<File>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Entry {
+    time: String,
+    campaign: String,
+    group: Group,
+    email: String,
+}
+
+impl Log {
+    pub fn open(path: &Path) -> Result<Self, csv::Error> {
+        // Read any previous sends
+        let mut sent = HashSet::new();
+        if path.exists() {
+            for entry in csv::Reader::from_path(path)?.deserialize() {
+                let Entry {
+                    campaign, email, ..
+                } = entry?;
+                sent.insert((campaign, email.to_lowercase()));
+            }
+        }
+        // Append further sends
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let empty = file.metadata()?.len() == 0;
+        let writer = csv::WriterBuilder::new()
+            .has_headers(empty)
+            .from_writer(file);
+
+        Ok(Self { sent, writer })
+    }
+
+    pub fn sent(&self, campaign: &str, email: &str) -> bool {
+        self.sent
+            .contains(&(campaign.to_string(), email.to_lowercase()))
+    }
+
+    pub fn record(&mut self, campaign: &str, group: Group, email: &str) -> Result<(), csv::Error> {
+        self.writer.serialize(Entry {
+            time: Utc::now().to_rfc3339(),
+            campaign: campaign.to_string(),
+            group,
+            email: email.to_string(),
+        })?;
+        // Flush each send, so none are lost if interrupted
+        self.writer.flush()?;
+        self.sent
+            .insert((campaign.to_string(), email.to_lowercase()));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+use lettre::address::AddressError;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Deserialize;
+use thiserror::Error;
+
+pub mod campaign;
+pub mod notify;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Smtp {
+    pub host: String,
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub tls: Tls,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    #[serde(default)]
+    pub notify: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tls {
+    /// Plain connection, e.g. to a local relay.
+    None,
+    /// Upgrade the connection with STARTTLS.
+    #[default]
+    Starttls,
+    /// Connect over TLS from the start.
+    Wrapper,
+}
+
+#[derive(Clone)]
+pub struct Mailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl Mailer {
+    pub fn new(smtp: &Smtp) -> Result<Self, Error> {
+        // Connect to the relay
+        let mut builder = match smtp.tls {
+            Tls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.host),
+            Tls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host)?,
+            Tls::Wrapper => AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.host)?,
+        };
+        if let Some(port) = smtp.port {
+            builder = builder.port(port);
+        }
+        // Authenticate (if configured)
+        if let (Some(username), Some(password)) = (&smtp.username, &smtp.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: smtp.from.parse()?,
+        })
+    }
+
+    pub fn message(&self, to: &[&str], subject: &str, body: String) -> Result<Message, Error> {
+        message(&self.from, to, subject, body)
+    }
+
+    pub async fn send(&self, msg: Message) -> Result<(), Error> {
+        self.transport.send(msg).await?;
+        Ok(())
+    }
+}
+
+pub fn message(from: &Mailbox, to: &[&str], subject: &str, body: String) -> Result<Message, Error> {
+    let mut builder = Message::builder().from(from.clone());
+    for to in to {
+        builder = builder.to(to.parse()?);
+    }
+    Ok(builder
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(body)?)
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Address(#[from] AddressError),
+    #[error(transparent)]
+    Message(#[from] lettre::error::Error),
+    #[error(transparent)]
+    Smtp(#[from] lettre::transport::smtp::Error),
+    #[error(transparent)]
+    Template(#[from] askama::Error),
+}
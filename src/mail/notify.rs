@@ -0,0 +1,167 @@
+use std::time::Duration;
+
+use askama::Template;
+use lettre::Message;
+use log::{debug, error, info, warn};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::watch;
+use tokio::task::{JoinHandle, JoinSet};
+use tokio::time;
+
+use super::{Error, Mailer};
+use crate::db::event::Schedule;
+use crate::db::guest::Guest;
+use crate::db::history::Revision;
+use crate::db::menu::Menu;
+
+/// Attempts made to deliver each message.
+const ATTEMPTS: u32 = 5;
+
+/// Delay before the first retry, doubling after each.
+const BACKOFF: Duration = Duration::from_secs(2);
+
+#[derive(Debug)]
+pub struct Change {
+    guest: Guest,
+    revision: Revision,
+    menu: Menu,
+    schedule: Schedule,
+}
+
+impl Change {
+    pub fn new(guest: Guest, revision: Revision, menu: Menu, schedule: Schedule) -> Self {
+        Self {
+            guest,
+            revision,
+            menu,
+            schedule,
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "mail/notify.txt")]
+struct Summary<'a> {
+    guest: &'a Guest,
+    revision: &'a Revision,
+    menu: &'a Menu,
+    schedule: &'a Schedule,
+}
+
+#[derive(Template)]
+#[template(path = "mail/confirm.txt")]
+struct Confirmation<'a> {
+    guest: &'a Guest,
+    revision: &'a Revision,
+    menu: &'a Menu,
+    schedule: &'a Schedule,
+}
+
+#[derive(Clone, Debug)]
+pub struct Notifier {
+    tx: UnboundedSender<Change>,
+}
+
+impl Notifier {
+    pub fn send(&self, change: Change) {
+        if self.tx.send(change).is_err() {
+            warn!("notify: closed, dropping change");
+        }
+    }
+}
+
+pub fn spawn(mailer: Mailer, couple: Vec<String>) -> (Notifier, JoinHandle<()>) {
+    // Connect the notifier to its task
+    let (tx, rx) = mpsc::unbounded_channel();
+    // Spawn the notifier task
+    (Notifier { tx }, tokio::spawn(run(mailer, couple, rx)))
+}
+
+async fn run(mailer: Mailer, couple: Vec<String>, mut rx: UnboundedReceiver<Change>) {
+    // Signal deliveries to stop retrying once closed
+    let (closed, _) = watch::channel(false);
+    let mut deliveries = JoinSet::new();
+    while let Some(change) = rx.recv().await {
+        let name = change.guest.user().name();
+        // Prepare the messages for this change
+        let msgs = match compose(&mailer, &couple, &change) {
+            Ok(msgs) => msgs,
+            Err(err) => {
+                error!("notify: `{name}`: {err}");
+                continue;
+            }
+        };
+        // Deliver each message concurrently, so retries don't hold up others
+        for msg in msgs {
+            let (mailer, name, closed) = (mailer.clone(), name.clone(), closed.subscribe());
+            deliveries.spawn(deliver(mailer, name, msg, closed));
+        }
+        // Clean up finished deliveries
+        while deliveries.try_join_next().is_some() {}
+    }
+    // Stop retrying, then wait for any deliveries in progress
+    closed.send_replace(true);
+    while deliveries.join_next().await.is_some() {}
+    debug!("notifier: closed");
+}
+
+fn compose(mailer: &Mailer, couple: &[String], change: &Change) -> Result<Vec<Message>, Error> {
+    let Change {
+        guest,
+        revision,
+        menu,
+        schedule,
+    } = change;
+    let mut msgs = Vec::new();
+    // Summarize the change for the couple
+    let subject = format!("RSVP: {}", guest.user());
+    let body = Summary {
+        guest,
+        revision,
+        menu,
+        schedule,
+    }
+    .render()?;
+    for to in couple {
+        msgs.push(mailer.message(&[to], &subject, body.clone())?);
+    }
+    // Confirm the reply with the guest (if they have an email)
+    if let Some(email) = guest.email() {
+        let body = Confirmation {
+            guest,
+            revision,
+            menu,
+            schedule,
+        }
+        .render()?;
+        msgs.push(mailer.message(&[email], "Thanks for your RSVP!", body)?);
+    }
+
+    Ok(msgs)
+}
+
+async fn deliver(mailer: Mailer, name: String, msg: Message, mut closed: watch::Receiver<bool>) {
+    let mut delay = BACKOFF;
+    for attempt in 1..=ATTEMPTS {
+        let err = match mailer.send(msg.clone()).await {
+            Ok(()) => {
+                info!("notify: sent: `{name}`");
+                return;
+            }
+            Err(err) => err,
+        };
+        // Give up after the last attempt, or when shutting down
+        if attempt == ATTEMPTS || *closed.borrow() {
+            error!("notify: `{name}`: {err}, giving up");
+            return;
+        }
+        warn!("notify: `{name}`: {err}, retrying in {}s", delay.as_secs());
+        tokio::select! {
+            () = time::sleep(delay) => delay *= 2,
+            _ = closed.changed() => {
+                error!("notify: `{name}`: {err}, giving up on shutdown");
+                return;
+            }
+        }
+    }
+}
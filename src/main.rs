@@ -6,46 +6,78 @@ use std::time::Duration;
 
 use axum::handler::HandlerWithoutStateExt;
 use axum::http::Request;
-use axum::routing::{get, get_service};
-use axum::Router;
-use axum_login::axum_sessions::{async_session, SessionLayer};
-use axum_login::{memory_store, AuthLayer, AuthUser};
+use axum::routing::{get, get_service, post};
+use axum::{middleware, Extension, Router};
+use axum_login::axum_sessions::async_session;
+use axum_login::{memory_store, AuthLayer};
 use axum_server::tls_rustls::RustlsConfig;
 use axum_server::Handle;
 use clap::{Parser, ValueHint};
-use color_eyre::eyre::{Context, Result};
+use color_eyre::eyre::{eyre, Context, Result};
 use log::{debug, error, info, trace, warn};
 use rand::Rng;
 use tokio::signal;
 use tokio::signal::unix::SignalKind;
 use tokio::sync::RwLock;
+use tokio::time;
 use tower_http::services::ServeDir;
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::EnvFilter;
 
+mod cmd;
+mod config;
 mod db;
+mod key;
+mod mail;
+mod metrics;
 mod srv;
 mod user;
 
+use crate::cmd::{Command, Task};
+use crate::config::Config;
+use crate::db::deadline::Deadline;
+use crate::db::event::Schedule;
+use crate::db::menu::Menu;
+use crate::db::seating::Tables;
+use crate::db::store::{Csv, Sqlite, Store};
 use crate::db::Database;
+use crate::mail::Mailer;
+use crate::srv::link::Signer;
+use crate::srv::reload::Reloader;
 use crate::srv::route::Incoming;
-use crate::srv::{error, route};
+use crate::srv::session::{self, FileStore};
+use crate::srv::{auth, error, route};
+use crate::user::{Role, User};
 
 /// Hannah & Zakhary's wedding server.
 #[derive(Parser)]
 #[command(author, version, about)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Path to input guestlist.
     #[arg(value_hint = ValueHint::FilePath)]
     guests: Option<PathBuf>,
 
+    /// Path to configuration file.
+    #[arg(short, long)]
+    #[arg(value_hint = ValueHint::FilePath)]
+    config: Option<PathBuf>,
+
     /// Path to output guestlist.
     #[arg(short, long)]
     #[arg(value_hint = ValueHint::FilePath)]
     out: Option<PathBuf>,
 
+    /// Path to SQLite database.
+    #[arg(long)]
+    #[arg(conflicts_with = "out")]
+    #[arg(value_hint = ValueHint::FilePath)]
+    db: Option<PathBuf>,
+
     /// Path to log output file.
     #[arg(long)]
     #[arg(value_hint = ValueHint::FilePath)]
@@ -71,9 +103,39 @@ struct Args {
     #[arg(long)]
     key: Option<PathBuf>,
 
+    /// Delay before flushing database updates (ms).
+    #[arg(long)]
+    #[arg(default_value_t = 500)]
+    flush_delay: u64,
+
     /// Disable RSVP system.
     #[arg(long)]
     lock: bool,
+
+    /// Disable RSVP system after a deadline (RFC 3339).
+    #[arg(long)]
+    rsvp_deadline: Option<Deadline>,
+
+    /// Require an invite code to login.
+    #[arg(long)]
+    require_code: bool,
+
+    /// Key file for signing sessions.
+    #[arg(long)]
+    #[arg(env = "SESSION_KEY")]
+    #[arg(value_hint = ValueHint::FilePath)]
+    session_key: Option<PathBuf>,
+
+    /// Directory for storing sessions.
+    #[arg(long)]
+    #[arg(value_hint = ValueHint::DirPath)]
+    sessions: Option<PathBuf>,
+
+    /// Key file for signing login links.
+    #[arg(long)]
+    #[arg(env = "LINK_KEY")]
+    #[arg(value_hint = ValueHint::FilePath)]
+    link_key: Option<PathBuf>,
 }
 
 #[tokio::main]
@@ -91,7 +153,6 @@ async fn main() -> Result<()> {
         let file = File::options()
             .append(true)
             .create(true)
-            .write(true)
             .open(path)
             .context("failed to open log file")?;
         let log = tracing_subscriber::fmt::layer()
@@ -117,27 +178,138 @@ async fn main() -> Result<()> {
         );
     }
 
-    // Initialize database
-    let mut db = match &args.guests {
+    // Load (optional) configuration
+    let config = match &args.config {
+        Some(path) => Config::load(path).context("failed to load config")?,
+        None => Config::default(),
+    };
+    let menu = Menu::new(config.meal.clone());
+    let schedule = Schedule::new(config.event.clone());
+
+    // Check the guestlist before loading it (if requested)
+    let task = match args.command {
+        Some(Command::Check(check)) => {
+            let path = args.guests.ok_or_else(|| eyre!("missing guestlist"))?;
+            return cmd::check::run(check, &path, &menu, &schedule);
+        }
+        Some(Command::Task(task)) => Some(task),
+        None => None,
+    };
+
+    // Open (optional) SQLite database
+    let sqlite = match &args.db {
         Some(path) => {
-            Database::try_from(path.as_path()).context("failed to initialize database")?
+            debug!("database: sqlite: `{}`", path.display());
+            Some(Sqlite::open(path).context("failed to open database")?)
         }
-        None => {
-            // Initialize empty database
-            warn!("no guestlist provided, login will not be possible");
-            Database::default()
+        None => None,
+    };
+    // Restore any guests saved to the database
+    let mut saved = match &sqlite {
+        Some(sqlite) => sqlite.read().context("failed to read database")?,
+        None => Vec::default(),
+    };
+    // Answer the schedule's events with any replies from before it
+    for guest in &mut saved {
+        schedule.migrate(guest);
+    }
+    let seed = sqlite.is_some() && saved.is_empty();
+    let restored = !saved.is_empty();
+
+    // Initialize database
+    let mut db = if restored {
+        // Initialize from saved guests
+        if args.guests.is_some() {
+            warn!("database already initialized, using guestlist only for reloads");
         }
+        Database::new(saved)
+    } else if let Some(path) = &args.guests {
+        Database::new(db::load(path, &menu, &schedule).context("failed to initialize database")?)
+    } else {
+        // Initialize empty database
+        warn!("no guestlist provided, login will not be possible");
+        Database::default()
     };
     info!("loaded {} guests", db.len());
-    db.locked = args.lock;
-    if db.locked {
+    db.menu = menu;
+    db.schedule = schedule;
+    let seated = db.schedule.seated().map(|event| event.id.clone());
+    db.tables = Tables::new(seated.unwrap_or_default(), config.table.clone());
+    db.lock = args.lock;
+    db.deadline = args.rsvp_deadline;
+    if db.locked() {
         warn!("database is locked");
+    } else if let Some(deadline) = db.deadline {
+        info!("database locks at: {deadline}");
     }
-    // Set (optional) database write path
-    db.path = args.out;
-    if let Some(path) = &db.path {
-        debug!("database: output path: `{}`", path.display());
+    db.admins = config
+        .admin
+        .iter()
+        .map(|admin| User::admin(admin.name.clone(), admin.password.clone()))
+        .collect();
+    if db.admins.is_empty() {
+        debug!("database: no admins configured");
+    }
+    db.require_code = args.require_code;
+    if db.require_code {
+        debug!("database: invite codes required");
     }
+    // Set (optional) database store
+    db.store = match (sqlite, args.out) {
+        (Some(sqlite), _) => Some(Arc::new(sqlite) as Arc<dyn Store>),
+        (None, Some(path)) => {
+            debug!("database: output path: `{}`", path.display());
+            Some(Arc::new(Csv::new(path, db.schedule.ids())))
+        }
+        (None, None) => None,
+    };
+    // Only the server and tasks that write may touch the store
+    if task.as_ref().is_none_or(Task::writes) {
+        // Seed a new database with the guestlist
+        if seed {
+            db.write().context("failed to seed database")?;
+        }
+        // Recover any updates that were not fully written
+        let recovered = db.recover().context("failed to recover database")?;
+        if recovered > 0 {
+            warn!("recovered {recovered} updates from journal");
+        }
+    } else {
+        // Read a snapshot, including updates not yet written
+        db.replay().context("failed to read journal")?;
+        db.store = None;
+    }
+
+    // Load (optional) login link key
+    let signer = match &args.link_key {
+        Some(path) => {
+            let key = key::load(path).context("failed to load link key")?;
+            Some(Signer::new(&key))
+        }
+        None => None,
+    };
+
+    // Run subcommand (if any)
+    if let Some(task) = task {
+        return task.run(&mut db, signer.as_ref(), &config).await;
+    }
+    // Track changes made before a restore, so reloads won't undo them
+    let reloadable = match &args.guests {
+        Some(path) if restored => match db::load(path, &db.menu, &db.schedule) {
+            Ok(guests) => {
+                let diverged = db.diverge(&guests);
+                if diverged > 0 {
+                    warn!("restored: {diverged} guests differ from the guestlist, reloads must match them");
+                }
+                true
+            }
+            Err(err) => {
+                error!("failed to read guestlist, disabling reloads: {err}");
+                false
+            }
+        },
+        guests => guests.is_some(),
+    };
 
     // Initialize tracing layer
     let trace = TraceLayer::new_for_http().on_request(|req: &Request<_>, _: &_| {
@@ -147,46 +319,136 @@ async fn main() -> Result<()> {
         trace!("{info}");
     });
 
-    // Initialize session layer
-    let secret: [u8; 64] = rand::thread_rng().gen();
-    let session = SessionLayer::new(async_session::MemoryStore::new(), &secret).with_secure(false);
+    // Initialize session secret
+    let secret = match &args.session_key {
+        Some(path) => key::load(path).context("failed to load session key")?,
+        None => {
+            if args.sessions.is_some() {
+                warn!("no session key provided, sessions will not survive restart");
+            }
+            rand::thread_rng().gen()
+        }
+    };
+    // Initialize session store
+    let sessions = match args.sessions {
+        Some(dir) => {
+            debug!("sessions: directory: `{}`", dir.display());
+            let store = FileStore::new(dir)
+                .await
+                .context("failed to open session directory")?;
+            store
+                .cleanup()
+                .await
+                .map_err(|err| eyre!(err))
+                .context("failed to clean up sessions")?;
+            Some(store)
+        }
+        None => None,
+    };
 
     // Initialize auth layer
-    let users = db
-        .iter()
-        .map(|user| (user.get_id(), user.clone()))
-        .collect();
-    let memory = Arc::new(RwLock::new(users));
-    let store = memory_store::MemoryStore::new(&memory);
+    let users: auth::Users = Arc::new(RwLock::new(auth::users(&db)));
+    let store = memory_store::MemoryStore::new(&users);
     let auth = AuthLayer::new(store, &secret);
 
+    // Spawn (optional) email notifier
+    let notifier = match &config.smtp {
+        Some(smtp) => {
+            debug!("notify: smtp: `{}`", smtp.host);
+            let mailer = Mailer::new(smtp).context("failed to configure smtp")?;
+            let (notifier, task) = mail::notify::spawn(mailer, smtp.notify.clone());
+            db.notifier = Some(notifier);
+            Some(task)
+        }
+        None => None,
+    };
+
     // Wrap database layer
     let db = Arc::new(RwLock::new(db));
+    // Spawn database writer
+    let writer = db::writer::spawn(db.clone(), Duration::from_millis(args.flush_delay)).await;
+    // Prepare guestlist reloads (unless ignored)
+    let reloader = args
+        .guests
+        .filter(|_| reloadable)
+        .map(|path| Reloader::new(path, db.clone(), users.clone()));
+
+    // Create a handle for the server
+    let handle = Handle::new();
 
     // Build our application with routes
     debug!("directory root: `{}`", &args.root.display());
+    let admin = Router::new()
+        .route("/", get(route::admin::home))
+        .route("/guests", get(route::admin::guests).post(route::admin::add))
+        .route(
+            "/guests/:guest",
+            get(route::admin::guest).post(route::admin::edit),
+        )
+        .route("/guests/:guest/history", get(route::admin::history))
+        .route("/guests/:guest/remove", post(route::admin::remove))
+        .route(
+            "/guests/:guest/revert/:revision",
+            post(route::admin::revert),
+        )
+        .route("/export/:format", get(route::admin::download))
+        .route("/reload", post(route::admin::reload))
+        .route(
+            "/seating",
+            get(route::admin::seating).post(route::admin::seat),
+        )
+        .route_layer(auth::RequireAuth::login_with_role_or_redirect(
+            Role::Admin..,
+            Arc::new("/admin/login".into()),
+            None,
+        ))
+        .route("/login", get(route::admin::login).post(route::admin::auth));
+    let api = Router::new()
+        .route("/group", get(route::api::group))
+        .route(
+            "/replies/:guest",
+            get(route::api::reply).put(route::api::update),
+        )
+        .route("/admin/guests", get(route::api::guests))
+        .route(
+            "/admin/guests/:guest",
+            get(route::api::record).put(route::api::edit),
+        )
+        .fallback(route::api::fallback);
     let app = Router::new()
         .route("/", get(route::home))
         .route("/about", get(route::about))
         .route("/dashboard", get(route::dashboard))
+        .route("/health", get(route::health))
+        .route("/metrics", get(route::metrics))
         .route("/login", get(route::login).post(route::auth))
+        .route("/login/link/:token", get(route::link))
         .route("/logout", get(route::logout))
         .route("/registry", get(route::registry))
         .route("/rsvp", get(route::rsvp).post(route::reply))
         .route("/travel", get(route::travel))
+        .nest("/admin", admin)
+        .nest("/api/v1", api)
+        .layer(middleware::from_fn(metrics::track))
         .layer(trace)
         .fallback_service(get_service(
             ServeDir::new(args.root).not_found_service(error::e404.into_service()),
         ))
-        .layer(auth)
-        .layer(session)
-        .with_state(db);
-
-    // Create a handle for the server
-    let handle = Handle::new();
+        .layer(auth);
+    // Add session layer
+    let app = match sessions {
+        Some(store) => app.layer(session::layer(store, &secret)),
+        None => app.layer(session::layer(async_session::MemoryStore::new(), &secret)),
+    }
+    .layer(Extension(signer))
+    .layer(Extension(users))
+    .layer(Extension(reloader.clone()))
+    .layer(Extension(handle.clone()))
+    .layer(Extension(config.metrics))
+    .with_state(db.clone());
 
     // Spawn a task to gracefully shutdown server
-    tokio::spawn(signal(handle.clone()));
+    tokio::spawn(signal(handle.clone(), reloader));
 
     // Run it
     let addr = SocketAddr::from(([0; 8], args.port));
@@ -212,13 +474,33 @@ async fn main() -> Result<()> {
             .unwrap();
     }
 
+    // Flush pending database updates
+    db.write().await.close();
+    if let Some(writer) = writer {
+        writer.await.context("failed to join database writer")?;
+    }
+    if let Some(notifier) = notifier {
+        // Don't wait long on a slow or unreachable relay
+        match time::timeout(Duration::from_secs(10), notifier).await {
+            Ok(res) => res.context("failed to join email notifier")?,
+            Err(_) => warn!("notify: timed out, dropping pending messages"),
+        }
+    }
+    let db = db.read().await;
+    if !db.status().healthy() {
+        // Make a final attempt to save the database
+        db.write().context("failed to save database")?;
+    }
+
     Ok(())
 }
 
-async fn signal(handle: Handle) {
+async fn signal(handle: Handle, reloader: Option<Reloader>) {
     // Prepare signal handlers
     let mut sigterm =
         signal::unix::signal(SignalKind::terminate()).expect("failed to install SIGTERM handler");
+    let mut sighup =
+        signal::unix::signal(SignalKind::hangup()).expect("failed to install SIGHUP handler");
 
     loop {
         tokio::select! {
@@ -233,6 +515,17 @@ async fn signal(handle: Handle) {
                 error!("terminating");
                 handle.shutdown();
             },
+            _ = sighup.recv() => {
+                // Reload the guestlist
+                match &reloader {
+                    Some(reloader) => {
+                        if let Err(err) = reloader.reload().await {
+                            error!("reload: {err}");
+                        }
+                    }
+                    None => warn!("no guestlist in use, ignoring reload"),
+                }
+            },
         }
     }
 }
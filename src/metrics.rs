@@ -0,0 +1,173 @@
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use axum::extract::MatchedPath;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use prometheus::{
+    register_histogram, register_histogram_vec, register_int_counter, register_int_counter_vec,
+    register_int_gauge, register_int_gauge_vec, Encoder, Histogram, HistogramVec, IntCounter,
+    IntCounterVec, IntGauge, IntGaugeVec, TextEncoder,
+};
+
+use serde::Deserialize;
+
+use crate::db::Database;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Scraper {
+    /// Bearer token that scrapers must present.
+    pub token: String,
+}
+
+impl Scraper {
+    pub fn verify(&self, token: &str) -> bool {
+        // Compare in constant time, so the token can't be guessed bytewise
+        let (a, b) = (self.token.as_bytes(), token.as_bytes());
+        a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+    }
+}
+
+static REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "wedding_http_requests_total",
+        "HTTP requests handled, by route and status.",
+        &["method", "route", "status"]
+    )
+    .unwrap()
+});
+
+static LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "wedding_http_request_duration_seconds",
+        "Time taken to handle HTTP requests, by route.",
+        &["method", "route"]
+    )
+    .unwrap()
+});
+
+static LOGINS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "wedding_logins_total",
+        "Login attempts, by kind and result.",
+        &["kind", "result"]
+    )
+    .unwrap()
+});
+
+static ATTENDANCE: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "wedding_rsvp_guests",
+        "Guests invited to each event, by their answer.",
+        &["event", "attend"]
+    )
+    .unwrap()
+});
+
+static MEALS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "wedding_rsvp_meals",
+        "Meals chosen for each event.",
+        &["event", "meal"]
+    )
+    .unwrap()
+});
+
+static WRITES: LazyLock<Histogram> = LazyLock::new(|| {
+    register_histogram!(
+        "wedding_db_write_duration_seconds",
+        "Time taken to write the database to its store."
+    )
+    .unwrap()
+});
+
+static FAILURES: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "wedding_db_write_failures_total",
+        "Writes of the database to its store that failed."
+    )
+    .unwrap()
+});
+
+static CONNECTIONS: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "wedding_connections",
+        "Connections currently open to the server."
+    )
+    .unwrap()
+});
+
+pub async fn track<B>(req: Request<B>, next: Next<B>) -> Response {
+    // Label the request by its route, not its full path
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("other", MatchedPath::as_str)
+        .to_string();
+    // Time the response
+    let start = Instant::now();
+    let res = next.run(req).await;
+    let elapsed = start.elapsed();
+    // Record the request
+    let status = res.status().as_u16().to_string();
+    REQUESTS
+        .with_label_values(&[&method, &route, &status])
+        .inc();
+    LATENCY
+        .with_label_values(&[&method, &route])
+        .observe(elapsed.as_secs_f64());
+
+    res
+}
+
+pub fn login(kind: &str, success: bool) {
+    let result = if success { "success" } else { "failure" };
+    LOGINS.with_label_values(&[kind, result]).inc();
+}
+
+pub fn write<T, E>(f: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+    // Time the write
+    let timer = WRITES.start_timer();
+    let res = f();
+    timer.observe_duration();
+    // Count any failure
+    if res.is_err() {
+        FAILURES.inc();
+    }
+
+    res
+}
+
+pub fn render(db: &Database, connections: usize) -> Result<String, prometheus::Error> {
+    // Tally the replies
+    ATTENDANCE.reset();
+    MEALS.reset();
+    for tally in db.stats().events {
+        let event = tally.event.id.as_str();
+        for (attend, count) in [
+            ("yes", tally.yes),
+            ("no", tally.no),
+            ("pending", tally.pending),
+        ] {
+            ATTENDANCE
+                .with_label_values(&[event, attend])
+                .set(count as i64);
+        }
+        for (meal, serving) in &tally.meals {
+            MEALS
+                .with_label_values(&[event, meal])
+                .set(serving.count as i64);
+        }
+    }
+    CONNECTIONS.set(connections as i64);
+    // Report writes even before the first
+    LazyLock::force(&WRITES);
+    LazyLock::force(&FAILURES);
+    // Encode all the metrics
+    let mut buf = Vec::new();
+    TextEncoder::new().encode(&prometheus::gather(), &mut buf)?;
+
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
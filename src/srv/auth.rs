@@ -1,15 +1,42 @@
-use axum_login::extractors;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum_login::axum_sessions::SessionHandle;
 use axum_login::memory_store::MemoryStore;
+use axum_login::{extractors, AuthUser, RequireAuthorizationLayer};
 use log::debug;
+use tokio::sync::RwLock;
+
+use crate::db::Database;
+use crate::user::{Role, User};
+
+pub type Context = extractors::AuthContext<String, User, MemoryStore<String, User>, Role>;
+
+pub type Session = SessionHandle;
 
-use crate::user::User;
+pub type RequireAuth = RequireAuthorizationLayer<String, User, Role>;
 
-pub type Context = extractors::AuthContext<String, User, MemoryStore<String, User>>;
+pub type Users = Arc<RwLock<HashMap<String, User>>>;
+
+pub fn users(db: &Database) -> HashMap<String, User> {
+    db.iter()
+        .chain(&db.admins)
+        .map(|user| (user.get_id(), user.clone()))
+        .collect()
+}
+
+pub async fn sync(users: &Users, db: &Database) {
+    // Rebuild the users from the database
+    *users.write().await = self::users(db);
+    debug!("users: {} loaded", db.len() + db.admins.len());
+}
 
-pub async fn login(mut auth: Context, user: User) {
-    if auth.current_user.is_some() {
-        logout(auth.clone()).await;
+pub async fn login(mut auth: Context, session: Session, user: User) {
+    if let Some(prev) = auth.current_user.as_ref() {
+        debug!("logout: `{prev}`");
     }
+    // Issue a new session identifier, so none set before login can be reused
+    session.write().await.regenerate();
     auth.login(&user).await.unwrap();
     debug!("login: `{user}`");
 }
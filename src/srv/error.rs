@@ -1,9 +1,10 @@
 #![allow(clippy::unused_async)]
 
 use askama::Template;
-use axum::extract::rejection::{FormRejection, QueryRejection};
+use axum::extract::rejection::{PathRejection, QueryRejection};
 use axum::http::StatusCode;
 use axum::response::{Html, IntoResponse, Response};
+use axum_extra::extract::FormRejection;
 use thiserror::Error;
 
 pub async fn e404() -> impl IntoResponse {
@@ -51,6 +52,12 @@ impl From<FormRejection> for Error {
     }
 }
 
+impl From<PathRejection> for Error {
+    fn from(_: PathRejection) -> Self {
+        Self::e400()
+    }
+}
+
 impl From<QueryRejection> for Error {
     fn from(_: QueryRejection) -> Self {
         Self::e400()
@@ -60,7 +67,7 @@ impl From<QueryRejection> for Error {
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
         match self.render() {
-            Ok(html) => Html(html).into_response(),
+            Ok(html) => (self.code, Html(html)).into_response(),
             Err(err) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!("500: Failed to render template: {err}"),
@@ -0,0 +1,81 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::db::Ident;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Clone)]
+pub struct Signer {
+    mac: HmacSha256,
+}
+
+impl Signer {
+    pub fn new(key: &[u8]) -> Self {
+        Self {
+            mac: HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any size"),
+        }
+    }
+
+    pub fn sign(&self, ident: &Ident, ttl: Duration) -> String {
+        // Compute the expiry time
+        let expiry = (SystemTime::now() + ttl)
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        // Prepare the payload
+        let mut data = Vec::with_capacity(56);
+        data.extend_from_slice(ident.as_bytes());
+        data.extend_from_slice(&expiry.to_be_bytes());
+        // Sign the payload
+        let mut mac = self.mac.clone();
+        mac.update(&data);
+        data.extend_from_slice(&mac.finalize().into_bytes());
+        // Encode the token
+        URL_SAFE_NO_PAD.encode(data)
+    }
+
+    pub fn verify(&self, token: &str) -> Result<Ident, Error> {
+        // Decode the token
+        let data = URL_SAFE_NO_PAD
+            .decode(token)
+            .map_err(|_| Error::Malformed)?;
+        if data.len() != 56 {
+            return Err(Error::Malformed);
+        }
+        let (data, tag) = data.split_at(24);
+        // Verify the signature
+        let mut mac = self.mac.clone();
+        mac.update(data);
+        mac.verify_slice(tag).map_err(|_| Error::Signature)?;
+        // Check the expiry time
+        let (ident, expiry) = data.split_at(16);
+        let expiry = u64::from_be_bytes(expiry.try_into().map_err(|_| Error::Malformed)?);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        if now > expiry {
+            return Err(Error::Expired);
+        }
+        // Extract the identifier
+        let ident = Uuid::from_slice(ident).map_err(|_| Error::Malformed)?;
+        Ok(ident.into())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("malformed token")]
+    Malformed,
+    #[error("invalid signature")]
+    Signature,
+    #[error("expired token")]
+    Expired,
+}
@@ -1,5 +1,8 @@
 pub mod auth;
 pub mod error;
+pub mod link;
+pub mod reload;
 pub mod route;
+pub mod session;
 
 pub use error::Error;
@@ -0,0 +1,47 @@
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use log::info;
+use tokio::sync::RwLock;
+use tokio::task;
+
+use super::auth;
+use crate::db::{self, Changes, Database, Error};
+
+#[derive(Clone, Debug)]
+pub struct Reloader {
+    path: PathBuf,
+    db: Arc<RwLock<Database>>,
+    users: auth::Users,
+}
+
+impl Reloader {
+    pub fn new(path: PathBuf, db: Arc<RwLock<Database>>, users: auth::Users) -> Self {
+        Self { path, db, users }
+    }
+
+    pub async fn reload(&self) -> Result<Changes, Error> {
+        // Read the guestlist
+        let (menu, schedule) = {
+            let db = self.db.read().await;
+            (db.menu.clone(), db.schedule.clone())
+        };
+        let path = self.path.clone();
+        let guests = task::spawn_blocking(move || db::load(&path, &menu, &schedule))
+            .await
+            .map_err(io::Error::other)??;
+        // Merge it into the database
+        let mut db = self.db.write().await;
+        let changes = db.reload(guests)?;
+        // Refresh the users who may login
+        auth::sync(&self.users, &db).await;
+        // Wait for the changes to be journaled, without holding the lock
+        let synced = db.synced();
+        drop(db);
+        synced.await?;
+        info!("reloaded: `{}`, {changes}", self.path.display());
+
+        Ok(changes)
+    }
+}
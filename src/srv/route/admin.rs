@@ -0,0 +1,344 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::header;
+use axum::response::{IntoResponse, Redirect};
+use axum::Extension;
+use log::{trace, warn};
+use serde::Deserialize;
+use tokio::sync::RwLock;
+
+use super::page::{Admin, AdminGuest, AdminGuests, AdminHistory, AdminLogin, AdminSeating};
+use super::{Answers, Form, Path};
+use crate::db::export::{self, Format};
+use crate::db::guest::{Diets, Events, Guest, Message, Reply};
+use crate::db::{Database, Group, Ident};
+use crate::metrics;
+use crate::srv::reload::Reloader;
+use crate::srv::{auth, Error};
+use crate::user::{Role, User};
+
+#[derive(Debug, Deserialize)]
+pub struct Credentials {
+    name: String,
+    password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Entry {
+    first: String,
+    last: String,
+    group: Group,
+    #[serde(default)]
+    child: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Details {
+    group: Group,
+    #[serde(default)]
+    child: bool,
+    #[serde(default)]
+    events: Events,
+    #[serde(default)]
+    plus_one: bool,
+    #[serde(default)]
+    diet: Diets,
+    note: Option<Message>,
+    msg: Option<Message>,
+}
+
+pub async fn home(State(db): State<Arc<RwLock<Database>>>) -> impl IntoResponse {
+    // Acquire database as a reader
+    let db = db.read().await;
+    // Present admin page
+    Admin::get(db.stats()).await
+}
+
+pub async fn login(auth: auth::Context) -> impl IntoResponse {
+    match auth.current_user {
+        // Redirect if already logged in as an admin
+        Some(user) if user.role == Role::Admin => Ok(Redirect::to("/admin")),
+        // Present login page
+        _ => Err(AdminLogin::get().await),
+    }
+}
+
+pub async fn auth(
+    State(db): State<Arc<RwLock<Database>>>,
+    auth: auth::Context,
+    Extension(session): Extension<auth::Session>,
+    Form(Credentials { name, password }): Form<Credentials>,
+) -> impl IntoResponse {
+    trace!("attempt: admin: `{name}`");
+    // Acquire database as a reader
+    let db = db.read().await;
+    // Query the admins using provided credentials
+    let Some(user) = db.admin(&name, &password).cloned() else {
+        // Admin not found
+        warn!("reject: admin: `{name}`");
+        metrics::login("admin", false);
+        // Return with error message on failure
+        let msg = "Hmm, that name or password is incorrect.".to_string();
+        return Err(AdminLogin::msg(msg).await);
+    };
+    // Authenticate user
+    metrics::login("admin", true);
+    auth::login(auth, session, user).await;
+    // Redirect onwards to admin
+    Ok(Redirect::to("/admin"))
+}
+
+pub async fn guests(State(db): State<Arc<RwLock<Database>>>) -> impl IntoResponse {
+    // Acquire database as a reader
+    let db = db.read().await;
+    // Present guests page
+    AdminGuests::get(roster(&db), db.menu.clone(), db.schedule.clone()).await
+}
+
+pub async fn add(
+    State(db): State<Arc<RwLock<Database>>>,
+    Extension(users): Extension<auth::Users>,
+    Form(Entry {
+        first,
+        last,
+        group,
+        child,
+    }): Form<Entry>,
+) -> impl IntoResponse {
+    // Acquire database as a writer
+    let mut db = db.write().await;
+    // Add the new guest
+    let guest = Guest::new(group, User::new(first, last), child);
+    if let Err(err) = db.insert(guest) {
+        warn!("reject: insert: {err}");
+        // Return with error message on failure
+        let msg = format!("Hmm, we couldn't add that guest: {err}");
+        let page = AdminGuests::msg(roster(&db), db.menu.clone(), db.schedule.clone(), msg);
+        return Err(page.await.into_response());
+    }
+    // Allow the guest to login
+    auth::sync(&users, &db).await;
+    // Wait for the changes to be journaled, without holding the lock
+    let synced = db.synced();
+    drop(db);
+    if let Err(err) = synced.await {
+        return Err(Error::e500(err).into_response());
+    }
+    // Redirect back to guests
+    Ok(Redirect::to("/admin/guests"))
+}
+
+pub async fn guest(
+    State(db): State<Arc<RwLock<Database>>>,
+    Path(ident): Path<Ident>,
+) -> impl IntoResponse {
+    // Acquire database as a reader
+    let db = db.read().await;
+    // Extract the guest to edit
+    let guest = db.guest(&ident).cloned().ok_or_else(Error::e404)?;
+    // Present guest page
+    Ok::<_, Error>(AdminGuest::get(guest, db.menu.clone(), db.schedule.clone()).await)
+}
+
+pub async fn edit(
+    State(db): State<Arc<RwLock<Database>>>,
+    auth: auth::Context,
+    Extension(users): Extension<auth::Users>,
+    Path(ident): Path<Ident>,
+    Answers(details, events): Answers<Details>,
+) -> impl IntoResponse {
+    let Some(admin) = auth.current_user else {
+        return Err(Error::e401());
+    };
+    // Acquire database as a writer
+    let mut db = db.write().await;
+    // Extract the guest to edit
+    db.guest(&ident).ok_or_else(Error::e404)?;
+    let companion = db.companion(&ident).is_some();
+    // Update the guest's details
+    db.edit(
+        &ident,
+        details.group,
+        details.child,
+        details.events,
+        details.plus_one,
+    )
+    .map_err(Error::e500)?;
+    let guest = db.guest(&ident).ok_or_else(Error::e404)?;
+    // Update the guest's reply, even when locked
+    let mut reply = Reply {
+        events,
+        diet: details.diet,
+        note: details.note,
+        msg: details.msg,
+    };
+    reply.validate(guest, &db.menu, &db.schedule);
+    db.amend(&ident, reply, &admin).map_err(Error::e500)?;
+    // Stop any companion dropped from logging in
+    if companion {
+        auth::sync(&users, &db).await;
+    }
+    // Wait for the changes to be journaled, without holding the lock
+    let synced = db.synced();
+    drop(db);
+    synced.await.map_err(Error::e500)?;
+    // Redirect back to guests
+    Ok(Redirect::to("/admin/guests"))
+}
+
+pub async fn history(
+    State(db): State<Arc<RwLock<Database>>>,
+    Path(ident): Path<Ident>,
+) -> impl IntoResponse {
+    // Acquire database as a reader
+    let db = db.read().await;
+    // Collect the guest's revisions, newest first
+    let guest = db.guest(&ident).cloned().ok_or_else(Error::e404)?;
+    let mut revisions: Vec<_> = db
+        .history(&ident)
+        .map(|(idx, revision)| (idx, revision.clone()))
+        .collect();
+    revisions.reverse();
+    // Present history page
+    Ok::<_, Error>(AdminHistory::get(guest, revisions, db.menu.clone(), db.schedule.clone()).await)
+}
+
+pub async fn revert(
+    State(db): State<Arc<RwLock<Database>>>,
+    auth: auth::Context,
+    Extension(users): Extension<auth::Users>,
+    Path((ident, idx)): Path<(Ident, usize)>,
+) -> impl IntoResponse {
+    let Some(admin) = auth.current_user else {
+        return Err(Error::e401());
+    };
+    // Acquire database as a writer
+    let mut db = db.write().await;
+    // Restore the reply from before the revision
+    let revision = db
+        .revision(idx)
+        .filter(|revision| revision.target == ident)
+        .ok_or_else(Error::e404)?;
+    trace!("revert: `{ident}`, to before: {}", revision.when());
+    let mut reply = revision.old.clone();
+    let guest = db.guest(&ident).ok_or_else(Error::e404)?;
+    reply.validate(guest, &db.menu, &db.schedule);
+    let companion = db.companion(&ident).is_some();
+    db.amend(&ident, reply, &admin).map_err(Error::e500)?;
+    // Stop any companion dropped from logging in
+    if companion {
+        auth::sync(&users, &db).await;
+    }
+    // Wait for the changes to be journaled, without holding the lock
+    let synced = db.synced();
+    drop(db);
+    synced.await.map_err(Error::e500)?;
+    // Redirect back to the history
+    Ok(Redirect::to(&format!("/admin/guests/{ident}/history")))
+}
+
+pub async fn remove(
+    State(db): State<Arc<RwLock<Database>>>,
+    Extension(users): Extension<auth::Users>,
+    Path(ident): Path<Ident>,
+) -> impl IntoResponse {
+    // Acquire database as a writer
+    let mut db = db.write().await;
+    // Remove the guest
+    db.remove(&ident).map_err(Error::e500)?;
+    // Prevent the guest from logging in
+    auth::sync(&users, &db).await;
+    // Wait for the changes to be journaled, without holding the lock
+    let synced = db.synced();
+    drop(db);
+    synced.await.map_err(Error::e500)?;
+    // Redirect back to guests
+    Ok::<_, Error>(Redirect::to("/admin/guests"))
+}
+
+pub async fn reload(
+    State(db): State<Arc<RwLock<Database>>>,
+    Extension(reloader): Extension<Option<Reloader>>,
+) -> impl IntoResponse {
+    // Reload the guestlist (if any)
+    let res = match &reloader {
+        Some(reloader) => reloader.reload().await.map_err(|err| err.to_string()),
+        None => Err("no guestlist in use".to_string()),
+    };
+    // Acquire database as a reader
+    let db = db.read().await;
+    match res {
+        // Present the changes on success
+        Ok(changes) => {
+            let note = format!("Reloaded the guestlist: {changes}.");
+            Ok(AdminGuests::note(roster(&db), db.menu.clone(), db.schedule.clone(), note).await)
+        }
+        // Return with error message on failure
+        Err(err) => {
+            warn!("reload: {err}");
+            let msg = format!("Hmm, we couldn't reload the guestlist: {err}");
+            Err(AdminGuests::msg(roster(&db), db.menu.clone(), db.schedule.clone(), msg).await)
+        }
+    }
+}
+
+pub async fn seating(State(db): State<Arc<RwLock<Database>>>) -> impl IntoResponse {
+    // Acquire database as a reader
+    let db = db.read().await;
+    // Present seating page
+    AdminSeating::get(db.chart(), roster(&db), db.tables.clone()).await
+}
+
+pub async fn seat(
+    State(db): State<Arc<RwLock<Database>>>,
+    Form(seats): Form<Vec<(Ident, String)>>,
+) -> impl IntoResponse {
+    // Acquire database as a writer
+    let mut db = db.write().await;
+    // Validate every seat before assigning any
+    for (ident, table) in &seats {
+        db.guest(ident).ok_or_else(Error::e404)?;
+        if !table.trim().is_empty() && db.tables.get(table).is_none() {
+            warn!("reject: seat: unknown table `{table}`");
+            return Err(Error::e400());
+        }
+    }
+    // Assign each guest to their table (or none, if empty)
+    for (ident, table) in seats {
+        db.seat(&ident, Some(table)).map_err(Error::e500)?;
+    }
+    // Wait for the changes to be journaled, without holding the lock
+    let synced = db.synced();
+    drop(db);
+    synced.await.map_err(Error::e500)?;
+    // Redirect back to seating
+    Ok::<_, Error>(Redirect::to("/admin/seating"))
+}
+
+pub async fn download(
+    State(db): State<Arc<RwLock<Database>>>,
+    Path(format): Path<Format>,
+) -> impl IntoResponse {
+    // Acquire database as a reader
+    let db = db.read().await;
+    // Export the database
+    let data = export::export(&db, format).map_err(Error::e500)?;
+    // Send it as an attachment
+    let disposition = format!("attachment; filename=\"{}\"", format.filename());
+    Ok::<_, Error>((
+        [
+            (header::CONTENT_TYPE, format.mime().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        data,
+    ))
+}
+
+fn roster(db: &Database) -> Vec<Guest> {
+    // Collect all guests in order of their group
+    db.groups()
+        .flat_map(|(_, idents)| idents)
+        .filter_map(|ident| db.guest(ident).cloned())
+        .collect()
+}
@@ -0,0 +1,407 @@
+use std::sync::Arc;
+
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::extract::{self, FromRequest, FromRequestParts, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use log::{trace, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+use crate::db::event::Schedule;
+use crate::db::guest::{Attend, Events, Guest, Reply};
+use crate::db::{self, Database, Group, Ident};
+use crate::srv::auth;
+use crate::user::{Role, User};
+
+#[derive(FromRequest)]
+#[from_request(via(extract::Json), rejection(Error))]
+pub struct Json<T>(T);
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        extract::Json(self.0).into_response()
+    }
+}
+
+#[derive(FromRequestParts)]
+#[from_request(via(extract::Path), rejection(Error))]
+pub struct Path<T>(T);
+
+#[derive(FromRequestParts)]
+#[from_request(via(extract::Query), rejection(Error))]
+pub struct Query<T>(T);
+
+/// Guest as seen by their group.
+#[derive(Debug, Serialize)]
+pub struct Entry {
+    id: Ident,
+    group: Group,
+    first: String,
+    last: String,
+    child: bool,
+    events: Vec<String>,
+    plus_one: bool,
+    host: Option<Ident>,
+    reply: Reply,
+}
+
+impl Entry {
+    fn new(guest: &Guest, schedule: &Schedule) -> Self {
+        Self {
+            id: guest.user().ident,
+            group: guest.group(),
+            first: guest.user().first().to_string(),
+            last: guest.user().last().to_string(),
+            child: guest.child(),
+            events: schedule
+                .invited(guest)
+                .map(|event| event.id.clone())
+                .collect(),
+            plus_one: guest.plus_one(),
+            host: guest.host().copied(),
+            reply: guest.reply().clone(),
+        }
+    }
+}
+
+/// Guest as seen by an admin.
+#[derive(Debug, Serialize)]
+pub struct Record {
+    #[serde(flatten)]
+    entry: Entry,
+    code: Option<String>,
+    email: Option<String>,
+    table: Option<String>,
+}
+
+impl Record {
+    fn new(guest: &Guest, schedule: &Schedule) -> Self {
+        Self {
+            entry: Entry::new(guest, schedule),
+            code: guest.code().map(ToString::to_string),
+            email: guest.email().map(ToString::to_string),
+            table: guest.table().map(ToString::to_string),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Party {
+    locked: bool,
+    deadline: Option<String>,
+    guests: Vec<Entry>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Filter {
+    group: Option<Group>,
+    event: Option<String>,
+    attend: Option<Attend>,
+    responded: Option<bool>,
+    child: Option<bool>,
+    table: Option<String>,
+    name: Option<String>,
+}
+
+impl Filter {
+    fn matches(&self, guest: &Guest) -> bool {
+        let reply = guest.reply();
+        // Match the answer to one event, or any of them
+        let attend = |attend: &Attend| match &self.event {
+            Some(event) => reply
+                .answer(event)
+                .is_some_and(|answer| answer.attend.as_ref() == Some(attend)),
+            None => reply
+                .events
+                .values()
+                .any(|answer| answer.attend.as_ref() == Some(attend)),
+        };
+        let name = |name: &str| {
+            guest
+                .user()
+                .name()
+                .to_lowercase()
+                .contains(&name.to_lowercase())
+        };
+        self.group.is_none_or(|group| guest.group() == group)
+            && self.event.as_ref().is_none_or(|event| guest.invited(event))
+            && self.attend.as_ref().is_none_or(attend)
+            && self
+                .responded
+                .is_none_or(|responded| reply.responded() == responded)
+            && self.child.is_none_or(|child| guest.child() == child)
+            && self
+                .table
+                .as_ref()
+                .is_none_or(|table| guest.table() == Some(table))
+            && self.name.as_deref().is_none_or(name)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Edit {
+    group: Option<Group>,
+    child: Option<bool>,
+    events: Option<Events>,
+    plus_one: Option<bool>,
+    table: Option<String>,
+    reply: Option<Reply>,
+}
+
+pub async fn group(
+    State(db): State<Arc<RwLock<Database>>>,
+    auth: auth::Context,
+) -> Result<Json<Party>, Error> {
+    let user = user(&auth)?;
+    // Acquire database as a reader
+    let db = db.read().await;
+    // Get all the guests in this user's group
+    let guests = party(&db, &user)?
+        .iter()
+        .filter_map(|ident| db.guest(ident))
+        .map(|guest| Entry::new(guest, &db.schedule))
+        .collect();
+
+    Ok(Json(Party {
+        locked: db.locked(),
+        deadline: db.deadline.map(|deadline| deadline.iso()),
+        guests,
+    }))
+}
+
+pub async fn reply(
+    State(db): State<Arc<RwLock<Database>>>,
+    auth: auth::Context,
+    Path(ident): Path<Ident>,
+) -> Result<Json<Reply>, Error> {
+    let user = user(&auth)?;
+    // Acquire database as a reader
+    let db = db.read().await;
+    // Confirm this user is in the requested guest's group
+    if !party(&db, &user)?.contains(&ident) {
+        warn!("unauthorized: `{user}`");
+        return Err(Error::e403());
+    }
+    // Present the guest's reply
+    let guest = db.guest(&ident).ok_or_else(Error::e404)?;
+    Ok(Json(guest.reply().clone()))
+}
+
+pub async fn update(
+    State(db): State<Arc<RwLock<Database>>>,
+    auth: auth::Context,
+    Extension(users): Extension<auth::Users>,
+    Path(ident): Path<Ident>,
+    Json(mut reply): Json<Reply>,
+) -> Result<Json<Reply>, Error> {
+    let user = user(&auth)?;
+    // Acquire database as a writer
+    let mut db = db.write().await;
+    // Do nothing if locked
+    if db.locked() {
+        return Err(Error::new(
+            StatusCode::LOCKED,
+            "RSVPs are closed".to_string(),
+        ));
+    }
+    // Confirm this user is in the requested guest's group
+    if !party(&db, &user)?.contains(&ident) {
+        warn!("unauthorized: `{user}`");
+        return Err(Error::e403());
+    }
+    // Update the guest's reply
+    let guest = db.guest(&ident).ok_or_else(Error::e404)?;
+    trace!("api: reply: `{user}`, for: `{}`", guest.user());
+    reply.validate(guest, &db.menu, &db.schedule);
+    let plus_one = guest.plus_one();
+    db.update(&ident, reply, None, &user).map_err(Error::e500)?;
+    // Stop any companion dropped from logging in
+    if plus_one {
+        auth::sync(&users, &db).await;
+    }
+    let reply = db.guest(&ident).ok_or_else(Error::e404)?.reply().clone();
+    // Wait for the changes to be journaled, without holding the lock
+    let synced = db.synced();
+    drop(db);
+    synced.await.map_err(Error::e500)?;
+    // Present the recorded reply
+    Ok(Json(reply))
+}
+
+pub async fn guests(
+    State(db): State<Arc<RwLock<Database>>>,
+    auth: auth::Context,
+    Query(filter): Query<Filter>,
+) -> Result<Json<Vec<Record>>, Error> {
+    admin(&auth)?;
+    // Acquire database as a reader
+    let db = db.read().await;
+    // Collect matching guests in order of their group
+    let guests = db
+        .groups()
+        .flat_map(|(_, idents)| idents)
+        .filter_map(|ident| db.guest(ident))
+        .filter(|guest| filter.matches(guest))
+        .map(|guest| Record::new(guest, &db.schedule))
+        .collect();
+
+    Ok(Json(guests))
+}
+
+pub async fn record(
+    State(db): State<Arc<RwLock<Database>>>,
+    auth: auth::Context,
+    Path(ident): Path<Ident>,
+) -> Result<Json<Record>, Error> {
+    admin(&auth)?;
+    // Acquire database as a reader
+    let db = db.read().await;
+    // Present the guest
+    let guest = db.guest(&ident).ok_or_else(Error::e404)?;
+    Ok(Json(Record::new(guest, &db.schedule)))
+}
+
+pub async fn edit(
+    State(db): State<Arc<RwLock<Database>>>,
+    auth: auth::Context,
+    Extension(users): Extension<auth::Users>,
+    Path(ident): Path<Ident>,
+    Json(edit): Json<Edit>,
+) -> Result<Json<Record>, Error> {
+    let admin = admin(&auth)?;
+    // Acquire database as a writer
+    let mut db = db.write().await;
+    // Validate every change before applying any
+    let guest = db.guest(&ident).ok_or_else(Error::e404)?;
+    trace!("api: edit: `{}`, by: `{admin}`", guest.user());
+    let group = edit.group.unwrap_or(guest.group());
+    let child = edit.child.unwrap_or(guest.child());
+    let events = edit.events.unwrap_or_else(|| guest.events().clone());
+    let plus_one = edit.plus_one.unwrap_or(guest.plus_one());
+    if let Some(table) = edit
+        .table
+        .as_deref()
+        .filter(|table| !table.trim().is_empty())
+    {
+        if db.tables.get(table).is_none() {
+            return Err(Error::e400(format!("unknown table: {table}")));
+        }
+    }
+    let reply = edit.reply.map(|mut reply| {
+        // Check the reply against the edited guest
+        let guest = guest.edited(group, child, events.clone(), plus_one);
+        reply.validate(&guest, &db.menu, &db.schedule);
+        reply
+    });
+    // Update the guest's details, keeping any not given
+    db.edit(&ident, group, child, events, plus_one)
+        .map_err(Error::e500)?;
+    // Seat the guest (if given)
+    if let Some(table) = edit.table {
+        db.seat(&ident, Some(table)).map_err(Error::e500)?;
+    }
+    // Update the guest's reply (if given), even when locked
+    if let Some(reply) = reply {
+        let companion = db.companion(&ident).is_some();
+        db.amend(&ident, reply, &admin).map_err(Error::e500)?;
+        // Stop any companion dropped from logging in
+        if companion {
+            auth::sync(&users, &db).await;
+        }
+    }
+    let guest = db.guest(&ident).ok_or_else(Error::e404)?;
+    let record = Record::new(guest, &db.schedule);
+    // Wait for the changes to be journaled, without holding the lock
+    let synced = db.synced();
+    drop(db);
+    synced.await.map_err(Error::e500)?;
+    // Present the updated guest
+    Ok(Json(record))
+}
+
+pub async fn fallback() -> impl IntoResponse {
+    Error::e404()
+}
+
+fn user(auth: &auth::Context) -> Result<User, Error> {
+    auth.current_user.clone().ok_or_else(Error::e401)
+}
+
+fn party<'a>(db: &'a Database, user: &User) -> Result<&'a [Ident], Error> {
+    // Only guests belong to a group
+    if user.role != Role::Guest {
+        warn!("unauthorized: `{user}`");
+        return Err(Error::e403());
+    }
+    db.group(&user.ident).map_err(|_| Error::e404())
+}
+
+fn admin(auth: &auth::Context) -> Result<User, Error> {
+    let user = user(auth)?;
+    if user.role != Role::Admin {
+        warn!("unauthorized: `{user}`");
+        return Err(Error::e403());
+    }
+    Ok(user)
+}
+
+#[derive(Debug, Error)]
+#[error("{msg}")]
+pub struct Error {
+    code: StatusCode,
+    msg: String,
+}
+
+impl Error {
+    fn new(code: StatusCode, msg: String) -> Self {
+        Self { code, msg }
+    }
+
+    fn e400(msg: String) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, msg)
+    }
+
+    fn e401() -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "not logged in".to_string())
+    }
+
+    fn e403() -> Self {
+        Self::new(StatusCode::FORBIDDEN, "not allowed".to_string())
+    }
+
+    fn e404() -> Self {
+        Self::new(StatusCode::NOT_FOUND, "not found".to_string())
+    }
+
+    fn e500(err: db::Error) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+    }
+}
+
+impl From<JsonRejection> for Error {
+    fn from(err: JsonRejection) -> Self {
+        Self::e400(err.body_text())
+    }
+}
+
+impl From<PathRejection> for Error {
+    fn from(err: PathRejection) -> Self {
+        Self::e400(err.body_text())
+    }
+}
+
+impl From<QueryRejection> for Error {
+    fn from(err: QueryRejection) -> Self {
+        Self::e400(err.body_text())
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        (self.code, extract::Json(json!({ "error": self.msg }))).into_response()
+    }
+}
@@ -1,35 +1,106 @@
+use std::collections::BTreeMap;
 use std::fmt::Display;
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+use axum::async_trait;
+use axum::body::HttpBody;
 use axum::extract::{self, ConnectInfo, FromRequest, FromRequestParts, State};
-use axum::http::{Method, Request, Uri};
+use axum::http::{header, HeaderMap, Method, Request, StatusCode, Uri};
 use axum::response::{IntoResponse, Redirect};
-use log::{error, trace, warn};
+use axum::{BoxError, Extension};
+use axum_server::Handle;
+use log::{trace, warn};
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use tokio::sync::RwLock;
 
 use self::page::{About, Dashboard, Home, Login, Registry, Rsvp, Travel};
+use super::link::Signer;
 use super::{auth, Error};
-use crate::db::guest::Reply;
+use crate::db::guest::{Answer, Diets, Guest, Message, Reply};
 use crate::db::{self, Database, Ident};
-use crate::user::User;
+use crate::metrics::{self, Scraper};
+use crate::user::{Role, User};
 
+pub mod admin;
+pub mod api;
 mod page;
 
 #[derive(FromRequest)]
-#[from_request(via(extract::Form), rejection(Error))]
+#[from_request(via(axum_extra::extract::Form), rejection(Error))]
 pub struct Form<T>(T);
 
+#[derive(FromRequestParts)]
+#[from_request(via(extract::Path), rejection(Error))]
+pub struct Path<T>(T);
+
 #[derive(FromRequestParts)]
 #[from_request(via(extract::Query), rejection(Error))]
 pub struct Query<T>(T);
 
+/// Form which also answers for each event.
+///
+/// Answers are submitted as `<event>.attend` and `<event>.meal` fields
+/// alongside the form's own.
+pub struct Answers<T>(T, BTreeMap<String, Answer>);
+
+#[async_trait]
+impl<S, B, T> FromRequest<S, B> for Answers<T>
+where
+    S: Send + Sync,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+    T: DeserializeOwned,
+{
+    type Rejection = Error;
+
+    async fn from_request(req: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        // Read the form
+        let body = String::from_request(req, state)
+            .await
+            .map_err(|_| Error::e400())?;
+        let form = serde_html_form::from_str(&body).map_err(|_| Error::e400())?;
+        // Collect each event's answers
+        let fields: Vec<(String, String)> =
+            serde_html_form::from_str(&body).map_err(|_| Error::e400())?;
+        let mut reply = Reply::default();
+        for (key, value) in fields {
+            reply.set(&key, &value).map_err(|_| Error::e400())?;
+        }
+
+        Ok(Self(form, reply.events))
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Action {
     guest: Option<Ident>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct Response {
+    #[serde(default)]
+    diet: Diets,
+    note: Option<Message>,
+    msg: Option<Message>,
+    companion_first: Option<String>,
+    companion_last: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Credentials {
+    #[serde(flatten)]
+    user: User,
+    code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Invite {
+    code: Option<String>,
+}
+
 #[derive(Debug)]
 pub struct Incoming {
     addr: SocketAddr,
@@ -87,9 +158,11 @@ pub async fn about() -> impl IntoResponse {
     About::get().await
 }
 
-pub async fn home() -> impl IntoResponse {
+pub async fn home(State(db): State<Arc<RwLock<Database>>>) -> impl IntoResponse {
+    // Acquire database as a reader
+    let db = db.read().await;
     // Present homepage
-    Home::get().await
+    Home::get(db.deadline).await
 }
 
 pub async fn dashboard(
@@ -100,6 +173,10 @@ pub async fn dashboard(
     let Some(user) = auth.current_user.clone() else {
         return Err(Redirect::to("/login").into_response());
     };
+    // Redirect admins to their own area
+    if user.role == Role::Admin {
+        return Err(Redirect::to("/admin").into_response());
+    }
     // Acquire database as a reader
     let db = db.read().await;
     // Get all the guests in this user's group
@@ -107,43 +184,166 @@ pub async fn dashboard(
         .group(&user.ident)
         .map_err(|err| Error::e500(err).into_response())?
         .iter()
-        .map(|ident| {
-            db.guest(ident)
-                .ok_or_else(|| Error::e500(db::Error::Guest).into_response())
-                .cloned()
-        })
-        .collect::<Result<_, _>>()?;
+        .map(|ident| db.guest(ident).cloned())
+        .collect::<Option<_>>()
+        .ok_or_else(|| Error::e500(db::Error::Guest).into_response())?;
     // Present dashboard page
-    Ok(Dashboard::get(user, guests, db.locked).await)
+    Ok(Dashboard::get(
+        user,
+        guests,
+        db.locked(),
+        db.deadline,
+        db.menu.clone(),
+        db.schedule.clone(),
+    )
+    .await)
+}
+
+pub async fn health(State(db): State<Arc<RwLock<Database>>>) -> impl IntoResponse {
+    // Acquire database as a reader
+    let db = db.read().await;
+    // Report the status of the database writer
+    let status = db.status();
+    let flushed = status
+        .flushed()
+        .and_then(|time| time.elapsed().ok())
+        .map_or_else(
+            || "never".to_string(),
+            |age| format!("{}s ago", age.as_secs()),
+        );
+    match status.error() {
+        None => (StatusCode::OK, format!("ok, flushed: {flushed}")),
+        Some(err) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("error: {err}, flushed: {flushed}"),
+        ),
+    }
+}
+
+pub async fn metrics(
+    State(db): State<Arc<RwLock<Database>>>,
+    auth: auth::Context,
+    Extension(handle): Extension<Handle>,
+    Extension(scraper): Extension<Option<Scraper>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    // Only allow admins, or scrapers with the configured token
+    let admin = auth
+        .current_user
+        .is_some_and(|user| user.role == Role::Admin);
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let scraper = scraper
+        .zip(token)
+        .is_some_and(|(scraper, token)| scraper.verify(token));
+    if !admin && !scraper {
+        warn!("unauthorized: metrics");
+        let challenge = [(header::WWW_AUTHENTICATE, "Bearer")];
+        return Err((StatusCode::UNAUTHORIZED, challenge).into_response());
+    }
+    // Acquire database as a reader
+    let db = db.read().await;
+    // Report the server's metrics
+    let text = metrics::render(&db, handle.connection_count())
+        .map_err(|err| Error::e500(err).into_response())?;
+    Ok(([(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)], text))
 }
 
-pub async fn login(auth: auth::Context) -> impl IntoResponse {
+pub async fn login(
+    State(db): State<Arc<RwLock<Database>>>,
+    auth: auth::Context,
+    Extension(session): Extension<auth::Session>,
+    Query(invite): Query<Invite>,
+) -> impl IntoResponse {
+    // Acquire database as a reader
+    let db = db.read().await;
+    // Login using the invite code (if provided)
+    if let Some(code) = invite.code {
+        let Some(user) = db.redeem(&code).cloned() else {
+            // Invite not found
+            warn!("reject: invalid code");
+            metrics::login("code", false);
+            // Return with error message on failure
+            let msg = "Hmm, we couldn't find an invitation for that code.".to_string();
+            return Err(Login::msg(db.has_codes(), db.deadline, msg)
+                .await
+                .into_response());
+        };
+        // Authenticate user
+        metrics::login("code", true);
+        auth::login(auth, session, user).await;
+        // Redirect onwards to RSVP
+        return Ok(Redirect::to("/dashboard"));
+    }
     match auth.current_user {
         // Redirect if already logged in
         Some(_) => Ok(Redirect::to("/dashboard")),
         // Present login page
-        None => Err(Login::get().await),
+        None => Err(Login::get(db.has_codes(), db.deadline)
+            .await
+            .into_response()),
     }
 }
 
+pub async fn link(
+    State(db): State<Arc<RwLock<Database>>>,
+    auth: auth::Context,
+    Extension(session): Extension<auth::Session>,
+    Extension(signer): Extension<Option<Signer>>,
+    Path(token): Path<String>,
+) -> impl IntoResponse {
+    // Acquire database as a reader
+    let db = db.read().await;
+    // Verify the login token
+    let user = match signer.as_ref().map(|signer| signer.verify(&token)) {
+        Some(Ok(ident)) => db.guest(&ident).map(Guest::user).cloned(),
+        Some(Err(err)) => {
+            warn!("reject: link: {err}");
+            None
+        }
+        None => None,
+    };
+    let Some(user) = user else {
+        metrics::login("link", false);
+        // Return with error message on failure
+        let msg = "Hmm, that login link is invalid or has expired.".to_string();
+        return Err(Login::msg(db.has_codes(), db.deadline, msg).await);
+    };
+    // Authenticate user
+    metrics::login("link", true);
+    auth::login(auth, session, user).await;
+    // Redirect onwards to RSVP
+    Ok(Redirect::to("/dashboard"))
+}
+
 pub async fn auth(
     State(db): State<Arc<RwLock<Database>>>,
     auth: auth::Context,
-    Form(user): Form<User>,
+    Extension(session): Extension<auth::Session>,
+    Form(Credentials { user, code }): Form<Credentials>,
 ) -> impl IntoResponse {
     // Sanitize user input
+    let code = code.filter(|code| !code.trim().is_empty());
     trace!("attempt: `{user}`");
     // Acquire database as a reader
     let db = db.read().await;
     // Query the database using provided credentials
-    let Some(user) = db.auth(&user).cloned() else {
+    let Some(user) = db.auth(&user, code.as_deref()).cloned() else {
         // User not found
         warn!("reject: `{user}`");
+        metrics::login("guest", false);
         // Return with error message on failure
-        return Err(Login::msg(format!("Hmm, we couldn't find a login for: {user}")).await);
+        let msg = match code {
+            Some(_) => format!("Hmm, that invite code doesn't match for: {user}"),
+            None => format!("Hmm, we couldn't find a login for: {user}"),
+        };
+        return Err(Login::msg(db.has_codes(), db.deadline, msg).await);
     };
     // Authenticate user
-    auth::login(auth, user).await;
+    metrics::login("guest", true);
+    auth::login(auth, session, user).await;
     // Redirect onwards to RSVP
     Ok(Redirect::to("/dashboard"))
 }
@@ -169,6 +369,10 @@ pub async fn rsvp(
     let Some(user) = auth.current_user.clone() else {
         return Err(Redirect::to("/login").into_response());
     };
+    // Redirect admins to their own area
+    if user.role == Role::Admin {
+        return Err(Redirect::to("/admin").into_response());
+    }
     // Present to the user if no guest supplied
     let guest = action.guest.unwrap_or(user.ident);
     // Acquire database as a reader
@@ -188,52 +392,101 @@ pub async fn rsvp(
         .guest(&guest)
         .ok_or_else(|| Error::e500(db::Error::Guest).into_response())?
         .clone();
+    let companion = db.companion(&guest.user().ident).cloned();
     // Present RSVP page
-    Ok(Rsvp::get(guest).await)
+    Ok(Rsvp::get(guest, companion, db.menu.clone(), db.schedule.clone()).await)
 }
 
 pub async fn reply(
     State(db): State<Arc<RwLock<Database>>>,
     auth: auth::Context,
+    Extension(users): Extension<auth::Users>,
     Query(action): Query<Action>,
-    Form(mut reply): Form<Reply>,
+    Answers(response, events): Answers<Response>,
 ) -> impl IntoResponse {
     // Do nothing if not logged in
     let Some(user) = auth.current_user else {
         // User not found, return status code
-        return Err(Error::e401());
+        return Err(Error::e401().into_response());
     };
+    // Redirect admins to their own area
+    if user.role == Role::Admin {
+        return Err(Redirect::to("/admin").into_response());
+    }
     // Do nothing if locked
-    if db.read().await.locked {
-        return Err(Error::e401());
+    if db.read().await.locked() {
+        return Err(Error::e401().into_response());
     }
     // Reply for the user if no guest supplied
     let guest = action.guest.unwrap_or(user.ident);
     // Acquire database as a writer
     let mut db = db.write().await;
     // Confirm this user is in the requested guest's group
-    let group = db.group(&user.ident).map_err(Error::e500)?;
+    let group = db
+        .group(&user.ident)
+        .map_err(|err| Error::e500(err).into_response())?;
     if !group.contains(&guest) {
         // Guest not in user's group
         warn!("unauthorized: `{user}`");
         // Present error page on failure
-        return Err(Error::e401());
+        return Err(Error::e401().into_response());
     }
     // Update this user's reply
-    trace!(
-        "reply: `{user}`, for: `{}`",
-        db.guest(&guest)
-            .ok_or_else(|| Error::e500(db::Error::Guest))?
-            .user()
-    );
-    reply.validate();
-    db.update(&guest, reply).map_err(Error::e500)?;
-    // Save the database to a file (optional)
-    // TODO: Should this be done async?
-    match db.write() {
-        Ok(_) | Err(db::Error::Path) => (),
-        Err(err) => error!("{err}"),
+    let target = db
+        .guest(&guest)
+        .ok_or_else(|| Error::e500(db::Error::Guest).into_response())?
+        .clone();
+    trace!("reply: `{user}`, for: `{}`", target.user());
+    let Response {
+        diet,
+        note,
+        msg,
+        companion_first,
+        companion_last,
+    } = response;
+    let mut reply = Reply {
+        events,
+        diet,
+        note,
+        msg,
+    };
+    reply.validate(&target, &db.menu, &db.schedule);
+    // Bring a companion (if named)
+    let first = companion_first.unwrap_or_default().trim().to_string();
+    let last = companion_last.unwrap_or_default().trim().to_string();
+    let named = !(first.is_empty() && last.is_empty());
+    let msg = if reply.attending() && named && (first.is_empty() || last.is_empty()) {
+        Some("Please give both a first and last name for your guest.".to_string())
+    } else {
+        // Update this user's reply, along with any companion
+        let companion = named.then(|| User::new(first, last));
+        match db.update(&guest, reply, companion, &user) {
+            Ok(()) => None,
+            Err(db::Error::Duplicate) => {
+                Some("Hmm, your guest is already on our guest list.".to_string())
+            }
+            Err(db::Error::PlusOne) => {
+                Some("Hmm, you've already told us who you're bringing.".to_string())
+            }
+            Err(err) => return Err(Error::e500(err).into_response()),
+        }
     };
+    // Return with error message on failure
+    if let Some(msg) = msg {
+        let companion = db.companion(&guest).cloned();
+        let page = Rsvp::msg(target, companion, db.menu.clone(), db.schedule.clone(), msg);
+        return Err(page.await.into_response());
+    }
+    // Allow any companion brought to log in, or dropped to no longer
+    if target.plus_one() {
+        auth::sync(&users, &db).await;
+    }
+    // Wait for the changes to be journaled, without holding the lock
+    let synced = db.synced();
+    drop(db);
+    synced
+        .await
+        .map_err(|err| Error::e500(err).into_response())?;
     // Redirect to the homepage
     Ok(Redirect::to("/dashboard"))
 }
@@ -4,7 +4,13 @@ use askama::Template;
 use axum::http::StatusCode;
 use axum::response::{Html, IntoResponse, Response};
 
+use crate::db::deadline::Deadline;
+use crate::db::event::Schedule;
 use crate::db::guest::Guest;
+use crate::db::history::Revision;
+use crate::db::menu::Menu;
+use crate::db::seating::{Chart, Tables};
+use crate::db::stats::Stats;
 use crate::user::User;
 
 macro_rules! add_impl {
@@ -24,7 +30,7 @@ macro_rules! add_impl {
     )*)
 }
 
-add_impl! { About Home Dashboard Login Registry Rsvp Travel }
+add_impl! { About Admin AdminGuest AdminGuests AdminHistory AdminLogin AdminSeating Home Dashboard Login Registry Rsvp Travel }
 
 #[derive(Template)]
 #[template(path = "about.html")]
@@ -41,17 +47,183 @@ impl About {
 }
 
 #[derive(Template)]
-#[template(path = "home.html")]
-pub struct Home;
+#[template(path = "admin.html")]
+pub struct Admin {
+    stats: Stats,
+}
 
-impl Home {
+impl Admin {
+    fn new(stats: Stats) -> Self {
+        Self { stats }
+    }
+
+    pub async fn get(stats: Stats) -> impl IntoResponse {
+        Self::new(stats)
+    }
+}
+
+#[derive(Template)]
+#[template(path = "admin/guest.html")]
+pub struct AdminGuest {
+    guest: Guest,
+    menu: Menu,
+    schedule: Schedule,
+}
+
+impl AdminGuest {
+    fn new(guest: Guest, menu: Menu, schedule: Schedule) -> Self {
+        Self {
+            guest,
+            menu,
+            schedule,
+        }
+    }
+
+    pub async fn get(guest: Guest, menu: Menu, schedule: Schedule) -> impl IntoResponse {
+        Self::new(guest, menu, schedule)
+    }
+}
+
+#[derive(Template)]
+#[template(path = "admin/guests.html")]
+pub struct AdminGuests {
+    msg: Option<String>,
+    note: Option<String>,
+    guests: Vec<Guest>,
+    menu: Menu,
+    schedule: Schedule,
+}
+
+impl AdminGuests {
+    fn new(guests: Vec<Guest>, menu: Menu, schedule: Schedule) -> Self {
+        Self {
+            msg: None,
+            note: None,
+            guests,
+            menu,
+            schedule,
+        }
+    }
+
+    pub async fn get(guests: Vec<Guest>, menu: Menu, schedule: Schedule) -> impl IntoResponse {
+        Self::new(guests, menu, schedule)
+    }
+
+    pub async fn msg(
+        guests: Vec<Guest>,
+        menu: Menu,
+        schedule: Schedule,
+        msg: String,
+    ) -> impl IntoResponse {
+        Self {
+            msg: Some(msg),
+            ..Self::new(guests, menu, schedule)
+        }
+    }
+
+    pub async fn note(
+        guests: Vec<Guest>,
+        menu: Menu,
+        schedule: Schedule,
+        note: String,
+    ) -> impl IntoResponse {
+        Self {
+            note: Some(note),
+            ..Self::new(guests, menu, schedule)
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "admin/seating.html")]
+pub struct AdminSeating {
+    chart: Chart,
+    guests: Vec<Guest>,
+    tables: Tables,
+}
+
+impl AdminSeating {
+    fn new(chart: Chart, guests: Vec<Guest>, tables: Tables) -> Self {
+        Self {
+            chart,
+            guests,
+            tables,
+        }
+    }
+
+    pub async fn get(chart: Chart, guests: Vec<Guest>, tables: Tables) -> impl IntoResponse {
+        Self::new(chart, guests, tables)
+    }
+}
+
+#[derive(Template)]
+#[template(path = "admin/history.html")]
+pub struct AdminHistory {
+    guest: Guest,
+    revisions: Vec<(usize, Revision)>,
+    menu: Menu,
+    schedule: Schedule,
+}
+
+impl AdminHistory {
+    fn new(
+        guest: Guest,
+        revisions: Vec<(usize, Revision)>,
+        menu: Menu,
+        schedule: Schedule,
+    ) -> Self {
+        Self {
+            guest,
+            revisions,
+            menu,
+            schedule,
+        }
+    }
+
+    pub async fn get(
+        guest: Guest,
+        revisions: Vec<(usize, Revision)>,
+        menu: Menu,
+        schedule: Schedule,
+    ) -> impl IntoResponse {
+        Self::new(guest, revisions, menu, schedule)
+    }
+}
+
+#[derive(Default, Template)]
+#[template(path = "admin/login.html")]
+pub struct AdminLogin {
+    msg: Option<String>,
+}
+
+impl AdminLogin {
     fn new() -> Self {
-        Self
+        Self::default()
     }
 
     pub async fn get() -> impl IntoResponse {
         Self::new()
     }
+
+    pub async fn msg(msg: String) -> impl IntoResponse {
+        Self { msg: Some(msg) }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "home.html")]
+pub struct Home {
+    deadline: Option<Deadline>,
+}
+
+impl Home {
+    fn new(deadline: Option<Deadline>) -> Self {
+        Self { deadline }
+    }
+
+    pub async fn get(deadline: Option<Deadline>) -> impl IntoResponse {
+        Self::new(deadline)
+    }
 }
 
 #[derive(Template)]
@@ -60,19 +232,39 @@ pub struct Dashboard {
     user: User,
     guests: Vec<Guest>,
     locked: bool,
+    deadline: Option<Deadline>,
+    menu: Menu,
+    schedule: Schedule,
 }
 
 impl Dashboard {
-    fn new(user: User, guests: Vec<Guest>, locked: bool) -> Self {
+    fn new(
+        user: User,
+        guests: Vec<Guest>,
+        locked: bool,
+        deadline: Option<Deadline>,
+        menu: Menu,
+        schedule: Schedule,
+    ) -> Self {
         Self {
             user,
             guests,
             locked,
+            deadline,
+            menu,
+            schedule,
         }
     }
 
-    pub async fn get(user: User, guests: Vec<Guest>, locked: bool) -> impl IntoResponse {
-        Self::new(user, guests, locked)
+    pub async fn get(
+        user: User,
+        guests: Vec<Guest>,
+        locked: bool,
+        deadline: Option<Deadline>,
+        menu: Menu,
+        schedule: Schedule,
+    ) -> impl IntoResponse {
+        Self::new(user, guests, locked, deadline, menu, schedule)
     }
 }
 
@@ -80,19 +272,29 @@ impl Dashboard {
 #[template(path = "login.html")]
 pub struct Login {
     msg: Option<String>,
+    code: bool,
+    deadline: Option<Deadline>,
 }
 
 impl Login {
-    fn new() -> Self {
-        Self::default()
+    fn new(code: bool, deadline: Option<Deadline>) -> Self {
+        Self {
+            msg: None,
+            code,
+            deadline,
+        }
     }
 
-    pub async fn get() -> impl IntoResponse {
-        Self::new()
+    pub async fn get(code: bool, deadline: Option<Deadline>) -> impl IntoResponse {
+        Self::new(code, deadline)
     }
 
-    pub async fn msg(msg: String) -> impl IntoResponse {
-        Self { msg: Some(msg) }
+    pub async fn msg(code: bool, deadline: Option<Deadline>, msg: String) -> impl IntoResponse {
+        Self {
+            msg: Some(msg),
+            code,
+            deadline,
+        }
     }
 }
 
@@ -114,15 +316,46 @@ impl Registry {
 #[template(path = "rsvp.html")]
 pub struct Rsvp {
     guest: Guest,
+    companion: Option<Guest>,
+    menu: Menu,
+    schedule: Schedule,
+    msg: Option<String>,
 }
 
 impl Rsvp {
-    fn new(guest: Guest) -> Self {
-        Self { guest }
+    fn new(
+        guest: Guest,
+        companion: Option<Guest>,
+        menu: Menu,
+        schedule: Schedule,
+        msg: Option<String>,
+    ) -> Self {
+        Self {
+            guest,
+            companion,
+            menu,
+            schedule,
+            msg,
+        }
+    }
+
+    pub async fn get(
+        guest: Guest,
+        companion: Option<Guest>,
+        menu: Menu,
+        schedule: Schedule,
+    ) -> impl IntoResponse {
+        Self::new(guest, companion, menu, schedule, None)
     }
 
-    pub async fn get(guest: Guest) -> impl IntoResponse {
-        Self::new(guest)
+    pub async fn msg(
+        guest: Guest,
+        companion: Option<Guest>,
+        menu: Menu,
+        schedule: Schedule,
+        msg: String,
+    ) -> impl IntoResponse {
+        Self::new(guest, companion, menu, schedule, Some(msg))
     }
 }
 
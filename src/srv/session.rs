@@ -0,0 +1,177 @@
+use std::io;
+use std::path::PathBuf;
+
+use axum_login::axum_sessions::async_session::{async_trait, Result, Session, SessionStore};
+use axum_login::axum_sessions::{SameSite, SessionLayer};
+use log::{debug, trace};
+use tokio::fs;
+
+pub fn layer<S: SessionStore>(store: S, secret: &[u8]) -> SessionLayer<S> {
+    // Withhold the cookie from cross-site form posts, so other sites can't
+    // submit forms (e.g. admin edits) as a logged-in user, while still sending
+    // it when following a link from elsewhere (e.g. a login link in an email)
+    SessionLayer::new(store, secret)
+        .with_same_site_policy(SameSite::Lax)
+        .with_secure(false)
+}
+
+#[derive(Clone, Debug)]
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl FileStore {
+    pub async fn new(dir: PathBuf) -> io::Result<Self> {
+        // Ensure the directory exists
+        fs::create_dir_all(&dir).await?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        // Make the identifier safe for use as a filename
+        let name = id.replace('/', "_").replace('+', "-");
+        self.dir.join(name).with_extension("json")
+    }
+
+    async fn read(&self, path: &PathBuf) -> Result<Option<Session>> {
+        match fs::read(path).await {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn remove(&self, path: &PathBuf) -> Result {
+        match fs::remove_file(path).await {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    pub async fn cleanup(&self) -> Result<usize> {
+        // Remove all expired sessions
+        let mut count = 0;
+        let mut entries = fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let expired = match self.read(&path).await {
+                Ok(Some(session)) => session.is_expired(),
+                Ok(None) => false,
+                // Remove unreadable sessions too
+                Err(_) => true,
+            };
+            if expired {
+                self.remove(&path).await?;
+                count += 1;
+            }
+        }
+        debug!("sessions: removed {count} expired");
+
+        Ok(count)
+    }
+}
+
+#[async_trait]
+impl SessionStore for FileStore {
+    async fn load_session(&self, cookie_value: String) -> Result<Option<Session>> {
+        let id = Session::id_from_cookie_value(&cookie_value)?;
+        trace!("session: load: `{id}`");
+        let path = self.path(&id);
+        // Read the session, discarding it if expired
+        let session = self.read(&path).await?;
+        match session.and_then(Session::validate) {
+            Some(session) => Ok(Some(session)),
+            None => {
+                self.remove(&path).await?;
+                Ok(None)
+            }
+        }
+    }
+
+    async fn store_session(&self, session: Session) -> Result<Option<String>> {
+        trace!("session: store: `{}`", session.id());
+        let path = self.path(session.id());
+        // Write the session atomically
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(&session)?).await?;
+        fs::rename(&tmp, &path).await?;
+
+        session.reset_data_changed();
+        Ok(session.into_cookie_value())
+    }
+
+    async fn destroy_session(&self, session: Session) -> Result {
+        trace!("session: destroy: `{}`", session.id());
+        self.remove(&self.path(session.id())).await
+    }
+
+    async fn clear_store(&self) -> Result {
+        trace!("session: clear");
+        let mut entries = fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            self.remove(&entry.path()).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use axum::routing::get;
+    use axum::{Extension, Router};
+    use axum_login::axum_sessions::async_session::MemoryStore;
+    use axum_login::{memory_store, AuthLayer};
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    use crate::db::guest::Guest;
+    use crate::db::Database;
+    use crate::srv::link::Signer;
+    use crate::srv::{auth, route};
+    use crate::user::User;
+
+    #[tokio::test]
+    async fn link_login() {
+        let secret = [7; 64];
+        let guest = Guest::new(1, User::new("Ann".into(), "Lee".into()), false);
+        let ident = guest.user().ident;
+        let db = Database::new(vec![guest]);
+        let users: auth::Users = Arc::new(RwLock::new(auth::users(&db)));
+        let signer = Signer::new(b"key");
+        let token = signer.sign(&ident, Duration::from_secs(60));
+        // Serve the login link along with the page it leads to
+        let app = Router::new()
+            .route("/login/link/:token", get(route::link))
+            .route("/dashboard", get(route::dashboard))
+            .layer(AuthLayer::new(
+                memory_store::MemoryStore::new(&users),
+                &secret,
+            ))
+            .layer(super::layer(MemoryStore::new(), &secret))
+            .layer(Extension(Some(signer)))
+            .with_state(Arc::new(RwLock::new(db)));
+        // Open the link, as when clicked in an email
+        let req = Request::get(format!("/login/link/{token}"))
+            .body(Body::empty())
+            .unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert_eq!(res.headers()[header::LOCATION], "/dashboard");
+        // Browsers only send a lax cookie when redirected from another site
+        let cookie = res.headers()[header::SET_COOKIE].to_str().unwrap();
+        assert!(cookie.contains("SameSite=Lax"), "{cookie}");
+        // Follow the redirect, still logged in
+        let session = cookie.split(';').next().unwrap();
+        let req = Request::get("/dashboard")
+            .header(header::COOKIE, session)
+            .body(Body::empty())
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
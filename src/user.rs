@@ -1,6 +1,7 @@
 use std::fmt::Display;
 use std::hash::Hash;
 
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
 use axum_login::secrecy::SecretVec;
 use axum_login::AuthUser;
 use itertools::Itertools;
@@ -15,6 +16,17 @@ pub struct User {
     pub(crate) ident: Ident,
     pub(super) first: String,
     pub(super) last: String,
+    #[serde(skip)]
+    pub(crate) role: Role,
+    #[serde(skip)]
+    hash: String,
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Role {
+    #[default]
+    Guest,
+    Admin,
 }
 
 impl User {
@@ -26,12 +38,27 @@ impl User {
         }
     }
 
-    #[allow(unused)]
+    pub fn admin(name: String, hash: String) -> Self {
+        Self {
+            first: name,
+            role: Role::Admin,
+            hash,
+            ..Default::default()
+        }
+    }
+
+    pub fn verify(&self, password: &str) -> bool {
+        PasswordHash::new(&self.hash).is_ok_and(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+    }
+
     pub fn first(&self) -> &str {
         self.first.as_ref()
     }
 
-    #[allow(unused)]
     pub fn last(&self) -> &str {
         self.last.as_ref()
     }
@@ -44,19 +71,26 @@ impl User {
     }
 }
 
-impl AuthUser<String> for User {
+impl AuthUser<String, Role> for User {
     fn get_id(&self) -> String {
-        self.name()
+        match self.role {
+            Role::Guest => self.ident.to_string(),
+            Role::Admin => format!("admin:{}", sanitize(&self.first)),
+        }
     }
 
     fn get_password_hash(&self) -> SecretVec<u8> {
-        SecretVec::new(Vec::default())
+        SecretVec::new(self.hash.clone().into_bytes())
+    }
+
+    fn get_role(&self) -> Option<Role> {
+        Some(self.role.clone())
     }
 }
 
 impl Display for User {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.get_id().fmt(f)
+        self.name().fmt(f)
     }
 }
 
@@ -74,6 +108,6 @@ impl PartialEq for User {
     }
 }
 
-fn sanitize(input: &str) -> String {
+pub(crate) fn sanitize(input: &str) -> String {
     input.trim().to_lowercase()
 }
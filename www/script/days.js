@@ -21,8 +21,9 @@ function update(elem, deadline) {
 }
 
 // Mark the deadline
-const deadline = new Date("2023-06-06T00:00:00");
 const elem = document.getElementById("days");
 
 // Set the initial countdown
-update(elem, deadline);
+if (elem) {
+    update(elem, new Date(elem.dataset.deadline));
+}